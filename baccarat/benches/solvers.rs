@@ -0,0 +1,132 @@
+use baccarat::calculation::{
+    incremental::IncrementalSolver, CachedSolver, Counter, ParallelSolver, Solver,
+};
+use baccarat::card::Card;
+use baccarat::game::SolverProvider;
+use baccarat::{Payouts, Rule};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+/// The number of rounds solved in each iteration.
+const ROUNDS: usize = 20;
+const CARDS_PER_ROUND: usize = 5;
+
+/// The rule of the sample config.
+fn get_rule() -> Rule {
+    Rule {
+        number_of_decks: 8,
+        cut_card_proportion: 0.85,
+        discard_at_start: true,
+        ez_baccarat: false,
+        chemin_de_fer: false,
+        tableau: Default::default(),
+        payouts: Payouts {
+            player_win: 1.0,
+            banker_win: 0.95,
+            tie: 8.0,
+            banker_win_on_total: vec![],
+            tie_on_total: None,
+            unsuit_pair: 11.0,
+            either_pair: 5.0,
+            perfect_pair: [25.0, 200.0],
+            bonus_unnatural: [1.0, 2.0, 4.0, 6.0, 10.0, 30.0],
+            bonus_natural_win: 1.0,
+            bonus_natural_tie: 0.0,
+            dragon_7: 40.0,
+            panda_8: 25.0,
+            big_small: None,
+            super_6: None,
+        },
+        side_bets: vec![],
+    }
+}
+
+/// The counters before each round of an 8-deck shoe, dealt in a fixed order so
+/// that every run solves the same rounds.
+fn get_counters() -> Vec<Counter> {
+    let mut counter = Counter::new(8);
+    let mut counters = Vec::with_capacity(ROUNDS);
+    for round in 0..ROUNDS {
+        counters.push(counter.clone());
+        for i in 0..CARDS_PER_ROUND {
+            let index = ((round * CARDS_PER_ROUND + i) * 17) % 52;
+            counter.remove_card(Card::from_index(index));
+        }
+    }
+    counters
+}
+
+fn solve_all<S: SolverProvider>(solver: &mut S, counters: &[Counter]) -> f64 {
+    counters
+        .iter()
+        .map(|counter| solver.solve(counter).sol_main.ex_banker_win)
+        .sum()
+}
+
+fn bench_solvers(c: &mut Criterion) {
+    let rule = get_rule();
+    let counters = get_counters();
+
+    // Every round has new cards, as in a simulation.
+    let mut group = c.benchmark_group("rounds of a shoe");
+    group.throughput(Throughput::Elements(ROUNDS as u64));
+    group.bench_function("simple", |b| {
+        let mut solver = Solver::new(&rule);
+        b.iter(|| solve_all(&mut solver, &counters))
+    });
+    group.bench_function("parallel", |b| {
+        let mut solver = ParallelSolver::new(&rule);
+        b.iter(|| solve_all(&mut solver, &counters))
+    });
+    group.bench_function("cached", |b| {
+        // Too small to keep the solutions from the previous iteration.
+        let mut solver = CachedSolver::new(Solver::new(&rule), ROUNDS / 2);
+        b.iter(|| solve_all(&mut solver, &counters))
+    });
+    group.bench_function("incremental", |b| {
+        let mut solver = IncrementalSolver::new(&rule, &counters[0]);
+        b.iter(|| solve_all(&mut solver, &counters))
+    });
+    group.finish();
+
+    // A single card leaves the shoe and comes back, as the assistant asks the
+    // service for a solution on every card entered or undone.
+    let card = Card::from_index(0);
+    let mut removed = counters[0].clone();
+    removed.remove_card(card);
+    let single_changes = [removed, counters[0].clone()];
+    let mut group = c.benchmark_group("one card removed");
+    group.throughput(Throughput::Elements(single_changes.len() as u64));
+    group.bench_function("simple", |b| {
+        let mut solver = Solver::new(&rule);
+        b.iter(|| solve_all(&mut solver, &single_changes))
+    });
+    group.bench_function("incremental", |b| {
+        let mut solver = IncrementalSolver::new(&rule, &counters[0]);
+        b.iter(|| solve_all(&mut solver, &single_changes))
+    });
+    group.finish();
+
+    // The same cards again, as in the service on undo or reload.
+    let mut group = c.benchmark_group("same cards again");
+    group.throughput(Throughput::Elements(ROUNDS as u64));
+    group.bench_with_input(
+        BenchmarkId::new("simple", ROUNDS),
+        &counters,
+        |b, counters| {
+            let mut solver = Solver::new(&rule);
+            b.iter(|| solve_all(&mut solver, counters))
+        },
+    );
+    group.bench_with_input(
+        BenchmarkId::new("cached", ROUNDS),
+        &counters,
+        |b, counters| {
+            let mut solver = CachedSolver::new(Solver::new(&rule), ROUNDS);
+            b.iter(|| solve_all(&mut solver, counters))
+        },
+    );
+    group.finish();
+}
+
+criterion_group!(benches, bench_solvers);
+criterion_main!(benches);
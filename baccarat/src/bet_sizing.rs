@@ -0,0 +1,743 @@
+use crate::{
+    calculation::{OutcomeDistribution, Solution},
+    game::{HandsBet, RoundOutcome},
+    side_bet::Winner,
+    Rule,
+};
+use serde::{Deserialize, Serialize};
+
+const MAX_ITERATIONS: usize = 100;
+
+/// What Solution::get_optimal_bets maximizes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Objective {
+    /// The expected logarithm of the bankroll, i.e., Kelly betting.
+    #[default]
+    Growth,
+    /// The expected profit. Every bet with a positive expectation gets as
+    /// much as allowed.
+    Ex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StakeOptions {
+    pub objective: Objective,
+    pub bankroll: f64,
+    /// The proportion of the full Kelly stakes to bet, e.g. 0.5 for half
+    /// Kelly. Only used when maximizing growth.
+    pub kelly_fraction: f64,
+    /// The table minimum of each bet. A bet is either not placed or at least
+    /// this much.
+    pub min_stake: f64,
+    /// The table maximum of each bet, or None if there's no limit.
+    pub max_stake: Option<f64>,
+}
+
+impl Default for StakeOptions {
+    fn default() -> Self {
+        Self {
+            objective: Objective::Growth,
+            bankroll: 1.0,
+            kelly_fraction: 1.0,
+            min_stake: 0.0,
+            max_stake: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct Stake {
+    pub bet: HandsBet,
+    pub stake: f64,
+}
+
+/// The joint distribution of the profits of 1 unit bet on each of several
+/// bets placed in the same round.
+///
+/// Bets decided by the final outcome (all but pair bets) are correlated
+/// exactly through Solution::outcomes. Pair bets depend on the ranks and suits
+/// of the first four cards instead, which are correlated exactly with the
+/// final outcome through Solution::pair_outcomes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BetDistribution {
+    /// Each entry is the profits of the bets (in the given order) and its
+    /// probability.
+    pub profits: Vec<(Vec<f64>, f64)>,
+}
+
+impl BetDistribution {
+    pub fn new(rule: &Rule, solution: &Solution, bets: &[HandsBet]) -> Self {
+        let has_pair_bets = bets
+            .iter()
+            .any(|bet| get_pair_profit(rule, bet, 0, 0).is_some());
+        if !has_pair_bets {
+            return Self::from_outcomes(rule, &solution.outcomes, bets, None);
+        }
+
+        let mut profits = vec![];
+        for (player, row) in solution.pair_outcomes.iter().enumerate() {
+            for (banker, outcomes) in row.iter().enumerate() {
+                let pairs = Self::from_outcomes(rule, outcomes, bets, Some((player, banker)));
+                profits.extend(pairs.profits);
+            }
+        }
+        // Pair bets are void too if the first four cards can't be dealt.
+        let p_void = 1.0 - profits.iter().map(|(_, p)| p).sum::<f64>();
+        if p_void > 1e-12 {
+            profits.push((vec![0.0; bets.len()], p_void));
+        }
+        Self { profits }
+    }
+
+    /// The profits in the given final outcomes, where the initial cards pair
+    /// as given if they're known.
+    fn from_outcomes(
+        rule: &Rule,
+        outcomes: &OutcomeDistribution,
+        bets: &[HandsBet],
+        pairs: Option<(usize, usize)>,
+    ) -> Self {
+        let get_profit = |bet: &HandsBet, outcome: Option<&RoundOutcome>| {
+            pairs
+                .and_then(|(player, banker)| get_pair_profit(rule, bet, player, banker))
+                .or_else(|| outcome.and_then(|outcome| get_profit_of_outcome(rule, bet, outcome)))
+                .unwrap_or(0.0)
+        };
+        let mut profits = vec![];
+        for (outcome, p) in outcomes.iter() {
+            if p <= 0.0 {
+                continue;
+            }
+            let x = bets
+                .iter()
+                .map(|bet| get_profit(bet, Some(&outcome)))
+                .collect();
+            profits.push((x, p));
+        }
+        // A void round is neither a win nor a loss, except for pair bets.
+        if outcomes.p_incomplete > 0.0 {
+            let x = bets.iter().map(|bet| get_profit(bet, None)).collect();
+            profits.push((x, outcomes.p_incomplete));
+        }
+        Self { profits }
+    }
+
+    /// Returns the expectation of the total profit of the given stakes.
+    pub fn get_ex(&self, stakes: &[f64]) -> f64 {
+        self.profits.iter().map(|(x, p)| p * dot(x, stakes)).sum()
+    }
+
+    /// Returns the expected logarithm of the bankroll (relative to the current
+    /// one) after betting the given fractions of the bankroll. It's -inf if
+    /// the bankroll may be lost completely.
+    pub fn get_growth_rate(&self, fractions: &[f64]) -> f64 {
+        self.profits
+            .iter()
+            .map(|(x, p)| p * (1.0 + dot(x, fractions)).ln())
+            .sum()
+    }
+
+    /// Returns the fractions of the bankroll to bet on each bet that maximize
+    /// the growth rate, i.e., the full Kelly bets. A bet is never shorted, so
+    /// bets with negative expectations usually get 0, unless they hedge other
+    /// bets well enough.
+    pub fn get_kelly_fractions(&self) -> Vec<f64> {
+        let n = self.profits.first().map_or(0, |(x, _)| x.len());
+        self.maximize_growth(&vec![0.0; n], &vec![f64::INFINITY; n])
+    }
+
+    /// The same as get_kelly_fractions(), but the fraction of bet i is kept
+    /// within [lower[i], upper[i]].
+    pub fn maximize_growth(&self, lower: &[f64], upper: &[f64]) -> Vec<f64> {
+        let n = lower.len();
+        let mut f = lower.to_vec();
+        let mut growth = self.get_growth_rate(&f);
+
+        // Newton's method, where the bets at a bound that would go beyond it
+        // are held there.
+        for _ in 0..MAX_ITERATIONS {
+            let (gradient, hessian) = self.get_gradient_and_hessian(&f);
+            let free: Vec<usize> = (0..n)
+                .filter(|&i| {
+                    (f[i] > lower[i] || gradient[i] > 0.0) && (f[i] < upper[i] || gradient[i] < 0.0)
+                })
+                .collect();
+            if free.is_empty() {
+                break;
+            }
+            let a = free
+                .iter()
+                .map(|&i| free.iter().map(|&j| hessian[i][j]).collect())
+                .collect();
+            let b = free.iter().map(|&i| -gradient[i]).collect();
+            let Some(step) = solve_linear_equations(a, b) else {
+                break;
+            };
+
+            // Halve the step until the growth rate improves.
+            let mut t = 1.0;
+            let next = loop {
+                let mut next = f.clone();
+                for (&i, d) in free.iter().zip(&step) {
+                    next[i] = (f[i] + t * d).clamp(lower[i], upper[i]);
+                }
+                let next_growth = self.get_growth_rate(&next);
+                if next_growth.is_finite() && next_growth >= growth {
+                    growth = next_growth;
+                    break Some(next);
+                }
+                t /= 2.0;
+                if t < 1e-12 {
+                    break None;
+                }
+            };
+            let Some(next) = next else {
+                break;
+            };
+            let change: f64 = next.iter().zip(&f).map(|(x, y)| (x - y).abs()).sum();
+            f = next;
+            if change < 1e-12 {
+                break;
+            }
+        }
+        f
+    }
+
+    /// Returns the stakes of the fractional Kelly bets, e.g., half Kelly when
+    /// kelly_fraction is 0.5.
+    pub fn get_kelly_stakes(&self, bankroll: f64, kelly_fraction: f64) -> Vec<f64> {
+        self.get_kelly_fractions()
+            .iter()
+            .map(|f| f * kelly_fraction * bankroll.max(0.0))
+            .collect()
+    }
+
+    fn get_gradient_and_hessian(&self, fractions: &[f64]) -> (Vec<f64>, Vec<Vec<f64>>) {
+        let n = fractions.len();
+        let mut gradient = vec![0.0; n];
+        let mut hessian = vec![vec![0.0; n]; n];
+        for (x, p) in &self.profits {
+            let w = 1.0 + dot(x, fractions);
+            for i in 0..n {
+                gradient[i] += p * x[i] / w;
+                for j in 0..n {
+                    hessian[i][j] -= p * x[i] * x[j] / (w * w);
+                }
+            }
+        }
+        (gradient, hessian)
+    }
+}
+
+/// Returns all the bets offered under the rule.
+pub fn get_offered_bets(rule: &Rule) -> Vec<HandsBet> {
+    let payouts = &rule.payouts;
+    let mut bets = vec![
+        HandsBet::PlayerWin,
+        HandsBet::BankerWin,
+        HandsBet::Tie,
+        HandsBet::PlayerUnsuitPair,
+        HandsBet::BankerUnsuitPair,
+        HandsBet::EitherPair,
+        HandsBet::PerfectPair,
+        HandsBet::PlayerBonus,
+        HandsBet::BankerBonus,
+    ];
+    if payouts.tie_on_total.is_some() {
+        bets.extend((0..10).map(HandsBet::TieOn));
+    }
+    if rule.ez_baccarat {
+        bets.extend([HandsBet::Dragon7, HandsBet::Panda8]);
+    }
+    if payouts.super_6.is_some() {
+        bets.push(HandsBet::Super6);
+    }
+    if payouts.big_small.is_some() {
+        bets.extend([HandsBet::Big, HandsBet::Small]);
+    }
+    bets.extend((0..rule.side_bets.len()).map(HandsBet::SideBet));
+    bets
+}
+
+/// Recommends the stakes of all the offered bets together, taking the
+/// correlation between them into account. Bets that shouldn't be placed are
+/// left out.
+///
+/// When maximizing growth, only the bets with positive expectations and the
+/// main bets (which may hedge them) are considered. For each bet whose Kelly
+/// stake is below the table minimum, either raising it to the minimum or
+/// dropping it is chosen, whichever grows faster.
+pub fn get_optimal_bets(rule: &Rule, solution: &Solution, options: &StakeOptions) -> Vec<Stake> {
+    let offered = get_offered_bets(rule);
+    let distribution = BetDistribution::new(rule, solution, &offered);
+    let mut unit = vec![0.0; offered.len()];
+    let mut exs = Vec::with_capacity(offered.len());
+    for i in 0..offered.len() {
+        unit[i] = 1.0;
+        exs.push(distribution.get_ex(&unit));
+        unit[i] = 0.0;
+    }
+    let max_stake = options.max_stake.unwrap_or(f64::INFINITY);
+    let bankroll = options.bankroll.max(0.0);
+
+    let mut stakes = vec![];
+    match options.objective {
+        Objective::Ex => {
+            let mut order: Vec<usize> = (0..offered.len()).filter(|&i| exs[i] > 0.0).collect();
+            order.sort_by(|&i, &j| exs[j].total_cmp(&exs[i]));
+            let mut remaining = bankroll;
+            for i in order {
+                let stake = max_stake.min(remaining);
+                if stake <= 0.0 || stake < options.min_stake {
+                    break;
+                }
+                stakes.push(Stake {
+                    bet: offered[i],
+                    stake,
+                });
+                remaining -= stake;
+            }
+        }
+        Objective::Growth => {
+            let scale = options.kelly_fraction * bankroll;
+            if scale <= 0.0 || exs.iter().all(|&ex| ex <= 0.0) {
+                return stakes;
+            }
+            let candidates: Vec<HandsBet> = offered
+                .iter()
+                .zip(&exs)
+                .filter(|(bet, &ex)| ex > 0.0 || is_main_bet(bet))
+                .map(|(bet, _)| *bet)
+                .collect();
+            let distribution = BetDistribution::new(rule, solution, &candidates);
+
+            // Work in fractions of the Kelly bankroll.
+            let n = candidates.len();
+            let min_fraction = options.min_stake / scale;
+            let mut lower = vec![0.0; n];
+            let mut upper = vec![max_stake / scale; n];
+            let mut f = distribution.maximize_growth(&lower, &upper);
+            loop {
+                let below_minimum = (0..n)
+                    .filter(|&i| lower[i] == 0.0 && f[i] > 0.0 && f[i] < min_fraction)
+                    .min_by(|&i, &j| f[i].total_cmp(&f[j]));
+                let Some(i) = below_minimum else {
+                    break;
+                };
+
+                let mut dropped = upper.clone();
+                dropped[i] = 0.0;
+                let f_dropped = distribution.maximize_growth(&lower, &dropped);
+                let mut raised = lower.clone();
+                raised[i] = min_fraction.min(upper[i]);
+                let f_raised = distribution.maximize_growth(&raised, &upper);
+                if distribution.get_growth_rate(&f_raised)
+                    > distribution.get_growth_rate(&f_dropped)
+                {
+                    (lower, f) = (raised, f_raised);
+                } else {
+                    (upper, f) = (dropped, f_dropped);
+                }
+            }
+
+            for (bet, f) in candidates.into_iter().zip(f) {
+                if f > 0.0 {
+                    stakes.push(Stake {
+                        bet,
+                        stake: f * scale,
+                    });
+                }
+            }
+        }
+    }
+    stakes
+}
+
+fn is_main_bet(bet: &HandsBet) -> bool {
+    matches!(
+        bet,
+        HandsBet::PlayerWin | HandsBet::BankerWin | HandsBet::Tie
+    )
+}
+
+/// Returns the profit of 1 unit bet in the given final outcome, or None if the
+/// bet isn't decided by the final outcome (i.e., pair bets). Bets not offered
+/// under the rule are returned, so their profit is 0.
+pub fn get_profit_of_outcome(rule: &Rule, bet: &HandsBet, outcome: &RoundOutcome) -> Option<f64> {
+    if !rule.is_offered(*bet) {
+        return Some(0.0);
+    }
+    let payouts = &rule.payouts;
+    let (pt, bt) = (outcome.player_total, outcome.banker_total);
+    let win_or_lose = |win: bool, payout: f64| if win { payout } else { -1.0 };
+    let profit = match *bet {
+        HandsBet::PlayerWin => match outcome.winner() {
+            Winner::Player => payouts.player_win,
+            Winner::Banker => -1.0,
+            Winner::Tie => 0.0,
+        },
+        HandsBet::Tie => win_or_lose(pt == bt, payouts.tie),
+        HandsBet::BankerWin => match outcome.winner() {
+            Winner::Player => -1.0,
+            Winner::Banker => rule.get_banker_win_payout(bt, outcome.banker_cards),
+            Winner::Tie => 0.0,
+        },
+
+        HandsBet::PlayerUnsuitPair
+        | HandsBet::BankerUnsuitPair
+        | HandsBet::EitherPair
+        | HandsBet::PerfectPair => return None,
+
+        HandsBet::PlayerBonus => get_bonus_profit(rule, pt, bt, outcome.is_player_natural()),
+        HandsBet::BankerBonus => get_bonus_profit(rule, bt, pt, outcome.is_banker_natural()),
+
+        HandsBet::TieOn(total) => {
+            let payout = payouts.tie_on_total.expect("Tie on total is not offered!");
+            win_or_lose(pt == bt && pt == total, payout[total as usize])
+        }
+        HandsBet::Dragon7 => win_or_lose(
+            pt < bt && bt == 7 && outcome.banker_cards == 3,
+            payouts.dragon_7,
+        ),
+        HandsBet::Panda8 => win_or_lose(
+            pt > bt && pt == 8 && outcome.player_cards == 3,
+            payouts.panda_8,
+        ),
+        HandsBet::Super6 => {
+            let payout = payouts.super_6.expect("Super 6 is not offered!");
+            win_or_lose(
+                pt < bt && bt == 6,
+                payout[outcome.banker_cards as usize - 2],
+            )
+        }
+        HandsBet::Big => {
+            let payout = payouts.big_small.expect("Big/Small is not offered!");
+            win_or_lose(outcome.player_cards + outcome.banker_cards >= 5, payout[0])
+        }
+        HandsBet::Small => {
+            let payout = payouts.big_small.expect("Big/Small is not offered!");
+            win_or_lose(outcome.player_cards + outcome.banker_cards == 4, payout[1])
+        }
+
+        HandsBet::SideBet(index) => {
+            let side_bet = &rule.side_bets[index];
+            match side_bet.settle(outcome) {
+                Some(i) => side_bet.outcomes[i].payout,
+                None => -1.0,
+            }
+        }
+
+        HandsBet::PlaceHolder => panic!("You cannot bet on PlaceHolder!"),
+    };
+    Some(profit)
+}
+
+/// The profit of a bonus bet on the side with total `own` against `other`.
+fn get_bonus_profit(rule: &Rule, own: u8, other: u8, is_natural: bool) -> f64 {
+    let payouts = &rule.payouts;
+    if own < other {
+        -1.0
+    } else if is_natural {
+        if own > other {
+            payouts.bonus_natural_win
+        } else {
+            payouts.bonus_natural_tie
+        }
+    } else {
+        match own - other {
+            ..=3 => -1.0,
+            delta => payouts.bonus_unnatural[(delta - 4) as usize],
+        }
+    }
+}
+
+/// Returns the profit of 1 unit on a pair bet, given how player's and
+/// banker's initial cards pair (see SolutionPair::p_joint), or None if it
+/// isn't a pair bet.
+fn get_pair_profit(rule: &Rule, bet: &HandsBet, player: usize, banker: usize) -> Option<f64> {
+    let payouts = &rule.payouts;
+    let win_or_lose = |win: bool, payout: f64| if win { payout } else { -1.0 };
+    let profit = match bet {
+        HandsBet::PlayerUnsuitPair => win_or_lose(player > 0, payouts.unsuit_pair),
+        HandsBet::BankerUnsuitPair => win_or_lose(banker > 0, payouts.unsuit_pair),
+        HandsBet::EitherPair => win_or_lose(player > 0 || banker > 0, payouts.either_pair),
+        HandsBet::PerfectPair => match (player == 2) as usize + (banker == 2) as usize {
+            0 => -1.0,
+            pairs => payouts.perfect_pair[pairs - 1],
+        },
+        _ => return None,
+    };
+    Some(profit)
+}
+
+fn dot(x: &[f64], y: &[f64]) -> f64 {
+    x.iter().zip(y).map(|(x, y)| x * y).sum()
+}
+
+/// Solves a * x = b by Gaussian elimination. Returns None if a is singular.
+fn solve_linear_equations(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    let n = b.len();
+    for col in 0..n {
+        let pivot = (col..n).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-15 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        let (upper, lower) = a.split_at_mut(col + 1);
+        let pivot_row = &upper[col];
+        for (row, b_row) in lower.iter_mut().zip(col + 1..) {
+            let factor = row[col] / pivot_row[col];
+            for (x, y) in row[col..].iter_mut().zip(&pivot_row[col..]) {
+                *x -= factor * y;
+            }
+            b[b_row] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.0; n];
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        calculation::{
+            tests::{assert_float_equal, get_typical_rule},
+            Counter, Solver,
+        },
+        card::{Card, Suit},
+    };
+
+    #[test]
+    fn test_kelly_fractions() {
+        // Even money with 60% to win.
+        let distribution = BetDistribution {
+            profits: vec![(vec![1.0], 0.6), (vec![-1.0], 0.4)],
+        };
+        assert_float_equal(distribution.get_kelly_fractions()[0], 0.2);
+
+        // Even money with pushes: f = (p - q) / (p + q).
+        let distribution = BetDistribution {
+            profits: vec![(vec![1.0], 0.5), (vec![0.0], 0.2), (vec![-1.0], 0.3)],
+        };
+        assert_float_equal(distribution.get_kelly_fractions()[0], 0.25);
+        let stakes = distribution.get_kelly_stakes(1000.0, 0.5);
+        assert_float_equal(stakes[0], 125.0);
+
+        // Two correlated bets.
+        let distribution = BetDistribution {
+            profits: vec![
+                (vec![1.0, 0.5], 0.4),
+                (vec![-1.0, 1.0], 0.35),
+                (vec![0.5, -1.0], 0.25),
+            ],
+        };
+        let f = distribution.get_kelly_fractions();
+        assert!(f[0] > 0.0 && f[1] > 0.0);
+        let growth = distribution.get_growth_rate(&f);
+        for g in [
+            [f[0] + 1e-3, f[1]],
+            [f[0] - 1e-3, f[1]],
+            [f[0], f[1] + 1e-3],
+            [f[0], f[1] - 1e-3],
+        ] {
+            assert!(distribution.get_growth_rate(&g) < growth);
+        }
+    }
+
+    #[test]
+    fn test_bet_distribution() {
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+        let solution = solver.solve(&Counter::new(8));
+
+        let bets = [
+            HandsBet::BankerWin,
+            HandsBet::BankerBonus,
+            HandsBet::PerfectPair,
+        ];
+        let distribution = BetDistribution::new(&rule, solution, &bets);
+        let p: f64 = distribution.profits.iter().map(|(_, p)| p).sum();
+        assert_float_equal(p, 1.0);
+        assert_float_equal(
+            distribution.get_ex(&[1.0, 0.0, 0.0]),
+            solution.sol_main.ex_banker_win,
+        );
+        assert_float_equal(
+            distribution.get_ex(&[0.0, 1.0, 0.0]),
+            solution.sol_bonus.ex_banker_bonus,
+        );
+        assert_float_equal(
+            distribution.get_ex(&[0.0, 0.0, 1.0]),
+            solution.sol_pair.ex_suit_pair,
+        );
+
+        // None of them is worth betting from a full shoe.
+        assert_eq!(distribution.get_kelly_fractions(), vec![0.0; 3]);
+    }
+
+    #[test]
+    fn test_correlated_pair_bets() {
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+
+        // Lots of 6s left make pairs likely.
+        let mut counter = Counter::new(1);
+        for _ in 0..4 {
+            for suit in [Suit::Diamond, Suit::Club, Suit::Heart, Suit::Spade] {
+                counter.add_card(Card::new(suit, 6));
+            }
+        }
+        let solution = solver.solve(&counter).clone();
+        let s = &solution.sol_pair;
+        let joint = &s.p_joint;
+        let p_player: f64 = joint[1].iter().chain(&joint[2]).sum();
+        let p_banker: f64 = joint.iter().map(|row| row[1] + row[2]).sum();
+        assert_float_equal(p_player, s.p_unsuit_pair);
+        assert_float_equal(p_banker, s.p_unsuit_pair);
+        assert_float_equal(1.0 - joint[0][0], s.p_either_pair);
+        assert_float_equal(joint[2][2], s.p_suit_pair[1]);
+
+        let bets = [
+            HandsBet::PlayerUnsuitPair,
+            HandsBet::EitherPair,
+            HandsBet::PerfectPair,
+        ];
+        assert!(solution.get_ex(bets[0]).unwrap() > 0.0);
+        assert!(solution.get_ex(bets[1]).unwrap() > 0.0);
+        let distribution = BetDistribution::new(&rule, &solution, &bets);
+        let p: f64 = distribution.profits.iter().map(|(_, p)| p).sum();
+        assert_float_equal(p, 1.0);
+        for (i, bet) in bets.iter().enumerate() {
+            let mut unit = [0.0; 3];
+            unit[i] = 1.0;
+            assert_float_equal(distribution.get_ex(&unit), solution.get_ex(*bet).unwrap());
+        }
+        // Either Pair wins whenever Player Pair or Perfect Pair wins.
+        for (x, _) in &distribution.profits {
+            assert!(x[0] < 0.0 || x[1] > 0.0);
+            assert!(x[2] < 0.0 || x[1] > 0.0);
+        }
+        // Both win exactly when player has a pair.
+        let p_both: f64 = distribution
+            .profits
+            .iter()
+            .filter(|(x, _)| x[0] > 0.0 && x[1] > 0.0)
+            .map(|(_, p)| p)
+            .sum();
+        assert_float_equal(p_both, s.p_unsuit_pair);
+
+        // Overlapping bets hedge nothing, so betting both at their own Kelly
+        // fractions would overbet. The joint fractions are smaller in total.
+        let pair_bets = [HandsBet::PlayerUnsuitPair, HandsBet::EitherPair];
+        let joint_fractions =
+            BetDistribution::new(&rule, &solution, &pair_bets).get_kelly_fractions();
+        let single_fractions: f64 = pair_bets
+            .iter()
+            .map(|bet| BetDistribution::new(&rule, &solution, &[*bet]).get_kelly_fractions()[0])
+            .sum();
+        assert!(joint_fractions.iter().sum::<f64>() < single_fractions);
+    }
+
+    #[test]
+    fn test_pair_bets_with_outcome() {
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+
+        // With only 4s and 9s left, player's initial cards total 8 if they
+        // pair, and 3 otherwise.
+        let mut counter = Counter::new(0);
+        for _ in 0..2 {
+            for suit in [Suit::Diamond, Suit::Club, Suit::Heart, Suit::Spade] {
+                counter.add_card(Card::new(suit, 4));
+                counter.add_card(Card::new(suit, 9));
+            }
+        }
+        let solution = solver.solve(&counter).clone();
+        let bets = [HandsBet::PlayerUnsuitPair, HandsBet::PlayerBonus];
+        let distribution = BetDistribution::new(&rule, &solution, &bets);
+        let p: f64 = distribution.profits.iter().map(|(_, p)| p).sum();
+        assert_float_equal(p, 1.0);
+        for (i, bet) in bets.iter().enumerate() {
+            let mut unit = [0.0; 2];
+            unit[i] = 1.0;
+            assert_float_equal(distribution.get_ex(&unit), solution.get_ex(*bet).unwrap());
+        }
+        // So Player Bonus never loses when Player Pair wins, with a natural 8.
+        let p_pair: f64 = distribution
+            .profits
+            .iter()
+            .filter(|(x, _)| x[0] > 0.0)
+            .map(|(x, p)| {
+                assert!(x[1] >= 0.0);
+                p
+            })
+            .sum();
+        assert_float_equal(p_pair, solution.sol_pair.p_unsuit_pair);
+    }
+
+    #[test]
+    fn test_optimal_bets() {
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+        let solution = solver.solve(&Counter::new(8));
+        assert!(solution
+            .get_optimal_bets(&rule, &Default::default())
+            .is_empty());
+
+        // Lots of 6s left favor the banker side.
+        let mut counter = Counter::new(1);
+        for _ in 0..4 {
+            for suit in [Suit::Diamond, Suit::Club, Suit::Heart, Suit::Spade] {
+                counter.add_card(Card::new(suit, 6));
+            }
+        }
+        let solution = solver.solve(&counter).clone();
+        let stakes = solution.get_optimal_bets(&rule, &Default::default());
+        assert!(!stakes.is_empty());
+        let total: f64 = stakes.iter().map(|s| s.stake).sum();
+        assert!(total > 0.0 && total < 1.0);
+
+        // The joint stakes grow at least as fast as any single Kelly bet.
+        let bets: Vec<HandsBet> = stakes.iter().map(|s| s.bet).collect();
+        let fractions: Vec<f64> = stakes.iter().map(|s| s.stake).collect();
+        let distribution = BetDistribution::new(&rule, &solution, &bets);
+        let growth = distribution.get_growth_rate(&fractions);
+        for bet in &bets {
+            let single = BetDistribution::new(&rule, &solution, &[*bet]);
+            let f = single.get_kelly_fractions();
+            assert!(single.get_growth_rate(&f) <= growth + 1e-12);
+        }
+
+        // A bet is either dropped or at least the table minimum.
+        let smallest = fractions.iter().cloned().fold(f64::INFINITY, f64::min);
+        let options = StakeOptions {
+            bankroll: 1000.0,
+            min_stake: smallest * 1000.0 * 1.5,
+            ..Default::default()
+        };
+        for stake in solution.get_optimal_bets(&rule, &options) {
+            assert!(stake.stake >= options.min_stake - 1e-9);
+        }
+
+        // Maximizing EX bets the table maximum on every positive bet, best
+        // first, as long as the bankroll allows.
+        let options = StakeOptions {
+            objective: Objective::Ex,
+            bankroll: 250.0,
+            max_stake: Some(100.0),
+            ..Default::default()
+        };
+        let stakes = solution.get_optimal_bets(&rule, &options);
+        let total: f64 = stakes.iter().map(|s| s.stake).sum();
+        assert!(total <= 250.0);
+        assert_float_equal(stakes[0].stake, 100.0);
+    }
+}
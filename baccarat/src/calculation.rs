@@ -1,12 +1,28 @@
 use serde::{Deserialize, Serialize};
 
-use crate::{card::Card, game::HandsBet, Rule};
+use crate::{
+    bet_sizing::{self, Stake, StakeOptions},
+    card::Card,
+    game::{HandsBet, RoundOutcome, SolverProvider},
+    side_bet::Winner,
+    tableau::Tableau,
+    Error, Rule,
+};
 use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+
+pub mod conditional;
+pub mod effect_of_removal;
+#[cfg(feature = "exact")]
+pub mod exact;
+pub mod incremental;
+pub mod lookahead;
 
 #[derive(Debug, Clone)]
 pub struct Solver<'a> {
     rule: &'a Rule,
     solution: Solution,
+    outcomes: OutcomeDistribution,
     counter: Counter,
 }
 
@@ -15,19 +31,166 @@ impl<'a> Solver<'a> {
         Self {
             rule,
             solution: Default::default(),
+            outcomes: Default::default(),
             counter: Counter::new(0),
         }
     }
 
     /// Note that this is NOT thread-safe.
     pub fn solve(&mut self, counter: &Counter) -> &Solution {
+        self.solve_with_outcomes(counter).0
+    }
+
+    /// The same as solve(), but returns an error rather than a meaningless
+    /// solution if there aren't enough cards to finish a round.
+    pub fn try_solve(&mut self, counter: &Counter) -> Result<&Solution, Error> {
+        Ok(self.try_solve_with_outcomes(counter)?.0)
+    }
+
+    /// The same as solve(), but also returns the distribution of the final
+    /// outcomes of the round, which can be used to price any bet that only
+    /// depends on the final totals and numbers of cards.
+    pub fn solve_with_outcomes(&mut self, counter: &Counter) -> (&Solution, &OutcomeDistribution) {
         self.counter = counter.clone();
-        functional::calculate(self.rule, &mut self.counter, &mut self.solution);
+        functional::calculate_with_outcomes(
+            self.rule,
+            &mut self.counter,
+            &mut self.solution,
+            &mut self.outcomes,
+        );
+        (&self.solution, &self.outcomes)
+    }
+
+    pub fn try_solve_with_outcomes(
+        &mut self,
+        counter: &Counter,
+    ) -> Result<(&Solution, &OutcomeDistribution), Error> {
+        functional::check_counter(counter)?;
+        Ok(self.solve_with_outcomes(counter))
+    }
+}
+
+impl<'a> SolverProvider for Solver<'a> {
+    fn solve(&mut self, counter: &Counter) -> &Solution {
+        Solver::solve(self, counter)
+    }
+}
+
+/// A solver that enumerates the rounds on all CPU cores. It only pays off
+/// with several cores, since splitting the work costs some time on one.
+#[derive(Debug, Clone)]
+pub struct ParallelSolver<'a> {
+    rule: &'a Rule,
+    solution: Solution,
+}
+
+impl<'a> ParallelSolver<'a> {
+    pub fn new(rule: &'a Rule) -> Self {
+        Self {
+            rule,
+            solution: Default::default(),
+        }
+    }
+
+    pub fn solve(&mut self, counter: &Counter) -> &Solution {
+        functional::par_calculate(self.rule, &mut counter.clone(), &mut self.solution);
         &self.solution
     }
+
+    pub fn try_solve(&mut self, counter: &Counter) -> Result<&Solution, Error> {
+        functional::check_counter(counter)?;
+        Ok(self.solve(counter))
+    }
 }
 
+impl<'a> SolverProvider for ParallelSolver<'a> {
+    fn solve(&mut self, counter: &Counter) -> &Solution {
+        ParallelSolver::solve(self, counter)
+    }
+}
+
+/// Wraps another solver, and reuses the solutions of the counters it has
+/// solved recently (see SolutionCache).
 #[derive(Debug, Clone)]
+pub struct CachedSolver<S> {
+    solver: S,
+    cache: SolutionCache,
+}
+
+impl<S: SolverProvider> CachedSolver<S> {
+    /// Keeps at most capacity solutions, but at least 1.
+    pub fn new(solver: S, capacity: usize) -> Self {
+        Self {
+            solver,
+            cache: SolutionCache::new(capacity.max(1)),
+        }
+    }
+
+    pub fn solve(&mut self, counter: &Counter) -> &Solution {
+        if !self.cache.contains(counter) {
+            let solution = self.solver.solve(counter).clone();
+            self.cache.insert(counter.clone(), solution);
+        }
+        self.cache.get(counter).unwrap()
+    }
+}
+
+impl<S: SolverProvider> SolverProvider for CachedSolver<S> {
+    fn solve(&mut self, counter: &Counter) -> &Solution {
+        CachedSolver::solve(self, counter)
+    }
+}
+
+/// Solutions keyed by the counter they're solved for. When it's full, the
+/// earliest inserted solution is forgotten first.
+///
+/// It suits the backend service, which is asked for the same counter again
+/// e.g. on undo or reload. A game rarely sees the same counter twice in a
+/// shoe, so caching mostly costs a simulation a little time.
+#[derive(Debug, Clone, Default)]
+pub struct SolutionCache {
+    capacity: usize,
+    solutions: HashMap<Counter, Solution>,
+    order: VecDeque<Counter>,
+}
+
+impl SolutionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            solutions: HashMap::with_capacity(capacity),
+            order: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn contains(&self, counter: &Counter) -> bool {
+        self.solutions.contains_key(counter)
+    }
+
+    pub fn get(&self, counter: &Counter) -> Option<&Solution> {
+        self.solutions.get(counter)
+    }
+
+    pub fn insert(&mut self, counter: Counter, solution: Solution) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.solutions.insert(counter.clone(), solution).is_none() {
+            if self.order.len() == self.capacity {
+                let earliest = self.order.pop_front().unwrap();
+                self.solutions.remove(&earliest);
+            }
+            self.order.push_back(counter);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.solutions.clear();
+        self.order.clear();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Counter {
     total: u32,
     bcr_value_count: [u32; 10],
@@ -52,7 +215,7 @@ impl Counter {
             total: 0,
             bcr_value_count: Default::default(),
             value_count: Default::default(),
-            card_count: card_count.clone(),
+            card_count: *card_count,
         };
         for (i, value) in card_count.iter().enumerate() {
             counter.total += *value;
@@ -67,6 +230,17 @@ impl Counter {
         counter
     }
 
+    /// The same as with_card_count(), but accepts a slice of any length and
+    /// returns an error if it doesn't have exactly 52 entries, or a count is
+    /// more than MAX_DECKS decks can hold.
+    pub fn try_with_card_count(card_count: &[u32]) -> Result<Self, Error> {
+        let card_count: &[u32; 52] = card_count
+            .try_into()
+            .map_err(|_| Error::InvalidCardCountLength(card_count.len()))?;
+        check_card_count(card_count)?;
+        Ok(Self::with_card_count(card_count))
+    }
+
     pub fn add_card(&mut self, card: Card) {
         self.total += 1;
         self.bcr_value_count[card.to_bcr_value_index()] += 1;
@@ -75,10 +249,20 @@ impl Counter {
     }
 
     pub fn remove_card(&mut self, card: Card) {
+        self.try_remove_card(card).unwrap();
+    }
+
+    /// The same as remove_card(), but returns an error rather than panics if
+    /// the card has run out. The counter is left untouched in that case.
+    pub fn try_remove_card(&mut self, card: Card) -> Result<(), Error> {
+        if self.card_count[card.to_index()] == 0 {
+            return Err(Error::CardNotFound(card));
+        }
         self.total -= 1;
         self.bcr_value_count[card.to_bcr_value_index()] -= 1;
         self.value_count[card.to_value_index()] -= 1;
         self.card_count[card.to_index()] -= 1;
+        Ok(())
     }
 
     pub fn get_total(&self) -> u32 {
@@ -98,14 +282,92 @@ impl Counter {
     }
 }
 
+/// Returns an error if a count is more than MAX_DECKS decks can hold.
+pub(crate) fn check_card_count(card_count: &[u32; 52]) -> Result<(), Error> {
+    match card_count.iter().position(|&count| count > MAX_DECKS) {
+        Some(i) => Err(Error::CardCountTooLarge(Card::from_index(i), card_count[i])),
+        None => Ok(()),
+    }
+}
+
+/// Besides the expectation (ex_*) of the profit of 1 unit bet, the variance
+/// (var_*) and skewness (skew_*) of the profit are given for each bet. The
+/// standard deviation is the square root of the variance.
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Solution {
+    /// Probability that the shoe runs out of cards before the round completes,
+    /// in which case the round is void and all bets are returned, except pair
+    /// bets, which are settled as long as the first four cards are dealt.
+    pub p_incomplete: f64,
+
     pub sol_main: SolutionMain,
     pub sol_pair: SolutionPair,
     pub sol_bonus: SolutionBonus,
+    pub sol_ez: Option<SolutionEz>, // Only available in EZ Baccarat.
+    pub sol_super_6: Option<SolutionSuper6>, // Only available when Super 6 is offered.
+    pub sol_big_small: Option<SolutionBigSmall>, // Only available when Big/Small is offered.
+    pub sol_chemin_de_fer: Option<SolutionCheminDeFer>, // Only available in Chemin de fer.
+    pub sol_side_bets: Vec<SolutionSideBet>,
+
+    /// Probabilities that the round deals 4, 5 or 6 cards respectively. They
+    /// add up to 1 - p_incomplete.
+    pub p_cards: [f64; 3],
+
+    /// The distribution of the final outcome of the round, which decides all
+    /// bets but pair bets. It's too large to be serialized.
+    #[serde(skip)]
+    pub outcomes: OutcomeDistribution,
+
+    /// The joint distribution of the pairs of the initial cards and the final
+    /// outcome, indexed by [player][banker] like SolutionPair::p_joint. They
+    /// add up to outcomes, and each of them, including its p_incomplete, adds
+    /// up to its entry of p_joint. Not serialized either.
+    #[serde(skip)]
+    pub pair_outcomes: [[OutcomeDistribution; 3]; 3],
 }
 
 impl Solution {
+    /// Recommends the stakes of all the offered bets together, maximizing
+    /// either the growth of the bankroll or the expected profit. See
+    /// bet_sizing::get_optimal_bets.
+    pub fn get_optimal_bets(&self, rule: &Rule, options: &StakeOptions) -> Vec<Stake> {
+        bet_sizing::get_optimal_bets(rule, self, options)
+    }
+
+    /// Returns the expected number of rounds (including this one) that start
+    /// before the cut card is reached, given the number of cards to deal until
+    /// it. See get_expected_rounds_remaining.
+    pub fn get_expected_rounds_remaining(&self, cards_before_cut: u32) -> f64 {
+        get_expected_rounds_remaining(&self.p_cards, cards_before_cut)
+    }
+
+    /// Returns the ex of the bet, or None if it isn't offered.
+    pub fn get_ex(&self, bet: HandsBet) -> Option<f64> {
+        let ex = match bet {
+            HandsBet::PlayerWin => self.sol_main.ex_player_win,
+            HandsBet::BankerWin => self.sol_main.ex_banker_win,
+            HandsBet::Tie => self.sol_main.ex_tie,
+            HandsBet::PlayerUnsuitPair => self.sol_pair.ex_unsuit_pair,
+            HandsBet::BankerUnsuitPair => {
+                let s = &self.sol_pair;
+                s.ex_banker_unsuit_pair.unwrap_or(s.ex_unsuit_pair)
+            }
+            HandsBet::EitherPair => self.sol_pair.ex_either_pair,
+            HandsBet::PerfectPair => self.sol_pair.ex_suit_pair,
+            HandsBet::PlayerBonus => self.sol_bonus.ex_player_bonus,
+            HandsBet::BankerBonus => self.sol_bonus.ex_banker_bonus,
+            HandsBet::TieOn(total) => self.sol_main.ex_tie_on_total?[total as usize],
+            HandsBet::Dragon7 => self.sol_ez.as_ref()?.ex_dragon_7,
+            HandsBet::Panda8 => self.sol_ez.as_ref()?.ex_panda_8,
+            HandsBet::Super6 => self.sol_super_6.as_ref()?.ex_super_6,
+            HandsBet::Big => self.sol_big_small.as_ref()?.ex_big,
+            HandsBet::Small => self.sol_big_small.as_ref()?.ex_small,
+            HandsBet::SideBet(i) => self.sol_side_bets.get(i)?.ex,
+            HandsBet::PlaceHolder => return None,
+        };
+        Some(ex)
+    }
+
     /// This function get the best main bet and side bet based on each bet's ex.
     /// Note that not all bet is taken into consideration. We only care those
     /// bets whose probabilities are greater than p_threshold.
@@ -151,67 +413,322 @@ impl Solution {
             (max_side_bet, max_side_ex) = (HandsBet::BankerBonus, s.ex_banker_bonus);
         }
 
+        // Check side bets (tie on total).
+        let s = &self.sol_main;
+        if let Some(ex_tie_on_total) = &s.ex_tie_on_total {
+            for (total, ex) in ex_tie_on_total.iter().enumerate() {
+                if s.p_tie_on_total[total] > p_threshold && max_side_ex < *ex {
+                    (max_side_bet, max_side_ex) = (HandsBet::TieOn(total as u8), *ex);
+                }
+            }
+        }
+
+        // Check side bets (EZ Baccarat).
+        if let Some(s) = &self.sol_ez {
+            if s.p_dragon_7 > p_threshold && max_side_ex < s.ex_dragon_7 {
+                (max_side_bet, max_side_ex) = (HandsBet::Dragon7, s.ex_dragon_7);
+            }
+            if s.p_panda_8 > p_threshold && max_side_ex < s.ex_panda_8 {
+                (max_side_bet, max_side_ex) = (HandsBet::Panda8, s.ex_panda_8);
+            }
+        }
+
+        // Check side bets (Super 6).
+        if let Some(s) = &self.sol_super_6 {
+            if s.p_super_6[0] + s.p_super_6[1] > p_threshold && max_side_ex < s.ex_super_6 {
+                (max_side_bet, max_side_ex) = (HandsBet::Super6, s.ex_super_6);
+            }
+        }
+
+        // Check side bets (Big/Small).
+        if let Some(s) = &self.sol_big_small {
+            if s.p_big > p_threshold && max_side_ex < s.ex_big {
+                (max_side_bet, max_side_ex) = (HandsBet::Big, s.ex_big);
+            }
+            if s.p_small > p_threshold && max_side_ex < s.ex_small {
+                (max_side_bet, max_side_ex) = (HandsBet::Small, s.ex_small);
+            }
+        }
+
+        // Check side bets declared in config.
+        for (i, s) in self.sol_side_bets.iter().enumerate() {
+            if s.p.iter().sum::<f64>() > p_threshold && max_side_ex < s.ex {
+                (max_side_bet, max_side_ex) = (HandsBet::SideBet(i), s.ex);
+            }
+        }
+
         ((max_main_bet, max_main_ex), (max_side_bet, max_side_ex))
     }
 
     fn calculate_ex_based_on_p(&mut self, rule: &Rule) {
         let payouts = &rule.payouts;
+        // A void round is neither a win nor a loss.
+        let p_complete = 1.0 - self.p_incomplete;
 
         // Calculate solution for main bets.
         let s = &mut self.sol_main;
         s.ex_player_win = s.p_player_win * payouts.player_win - s.p_banker_win;
-        s.ex_banker_win = s.p_banker_win * payouts.banker_win - s.p_player_win;
-        s.ex_tie = s.p_tie * payouts.tie - (1.0 - s.p_tie);
+        (s.var_player_win, s.skew_player_win) =
+            get_var_and_skew(&[(payouts.player_win, s.p_player_win), (-1.0, s.p_banker_win)]);
+        s.ex_banker_win = -s.p_player_win;
+        let mut banker_win = vec![(-1.0, s.p_player_win)];
+        for (cards, p) in s.p_banker_win_by_total.iter().enumerate() {
+            for (total, p) in p.iter().enumerate() {
+                let payout = rule.get_banker_win_payout(total as u8, cards as u8 + 2);
+                s.ex_banker_win += p * payout;
+                banker_win.push((payout, *p));
+            }
+        }
+        (s.var_banker_win, s.skew_banker_win) = get_var_and_skew(&banker_win);
+        s.ex_tie = s.p_tie * payouts.tie - (p_complete - s.p_tie);
+        (s.var_tie, s.skew_tie) =
+            get_var_and_skew(&[(payouts.tie, s.p_tie), (-1.0, p_complete - s.p_tie)]);
+        if let Some(payout) = payouts.tie_on_total {
+            let mut ex = [0.0; 10];
+            let mut var = [0.0; 10];
+            let mut skew = [0.0; 10];
+            for (i, &p) in s.p_tie_on_total.iter().enumerate() {
+                ex[i] = p * payout[i] - (p_complete - p);
+                (var[i], skew[i]) = get_var_and_skew(&[(payout[i], p), (-1.0, p_complete - p)]);
+            }
+            s.ex_tie_on_total = Some(ex);
+            s.var_tie_on_total = Some(var);
+            s.skew_tie_on_total = Some(skew);
+        }
 
         // Calculate solution for pair bets.
         let s = &mut self.sol_pair;
         s.ex_unsuit_pair = s.p_unsuit_pair * payouts.unsuit_pair - (1.0 - s.p_unsuit_pair);
+        (s.var_unsuit_pair, s.skew_unsuit_pair) = get_var_and_skew(&[
+            (payouts.unsuit_pair, s.p_unsuit_pair),
+            (-1.0, 1.0 - s.p_unsuit_pair),
+        ]);
+        s.ex_banker_unsuit_pair = s
+            .p_banker_unsuit_pair
+            .map(|p| p * payouts.unsuit_pair - (1.0 - p));
+        if let Some(p) = s.p_banker_unsuit_pair {
+            let (var, skew) = get_var_and_skew(&[(payouts.unsuit_pair, p), (-1.0, 1.0 - p)]);
+            s.var_banker_unsuit_pair = Some(var);
+            s.skew_banker_unsuit_pair = Some(skew);
+        }
         s.ex_either_pair = s.p_either_pair * payouts.either_pair - (1.0 - s.p_either_pair);
+        (s.var_either_pair, s.skew_either_pair) = get_var_and_skew(&[
+            (payouts.either_pair, s.p_either_pair),
+            (-1.0, 1.0 - s.p_either_pair),
+        ]);
         s.ex_suit_pair = s.p_suit_pair[0] * payouts.perfect_pair[0]
             + s.p_suit_pair[1] * payouts.perfect_pair[1]
             - (1.0 - s.p_suit_pair[0] - s.p_suit_pair[1]);
+        (s.var_suit_pair, s.skew_suit_pair) = get_var_and_skew(&[
+            (payouts.perfect_pair[0], s.p_suit_pair[0]),
+            (payouts.perfect_pair[1], s.p_suit_pair[1]),
+            (-1.0, 1.0 - s.p_suit_pair[0] - s.p_suit_pair[1]),
+        ]);
 
         // Calculate solution for bonus bets.
         let s = &mut self.sol_bonus;
         s.ex_player_bonus = s.p_player_bonus_natural_win * payouts.bonus_natural_win
             + s.p_bonus_natural_tie * payouts.bonus_natural_tie;
-        s.ex_banker_bonus = s.p_banker_bonus_natural_win as f64 * payouts.bonus_natural_win
+        s.ex_banker_bonus = s.p_banker_bonus_natural_win * payouts.bonus_natural_win
             + s.p_bonus_natural_tie * payouts.bonus_natural_tie;
-        let mut p_player_lose = 1.0 - s.p_player_bonus_natural_win - s.p_bonus_natural_tie;
-        let mut p_banker_lose = 1.0 - s.p_banker_bonus_natural_win - s.p_bonus_natural_tie;
+        let mut p_player_lose = p_complete - s.p_player_bonus_natural_win - s.p_bonus_natural_tie;
+        let mut p_banker_lose = p_complete - s.p_banker_bonus_natural_win - s.p_bonus_natural_tie;
+        let mut player_bonus = vec![
+            (payouts.bonus_natural_win, s.p_player_bonus_natural_win),
+            (payouts.bonus_natural_tie, s.p_bonus_natural_tie),
+        ];
+        let mut banker_bonus = vec![
+            (payouts.bonus_natural_win, s.p_banker_bonus_natural_win),
+            (payouts.bonus_natural_tie, s.p_bonus_natural_tie),
+        ];
         for i in 0..payouts.bonus_unnatural.len() {
             s.ex_player_bonus += s.p_player_bonus_unnatural[i] * payouts.bonus_unnatural[i];
             s.ex_banker_bonus += s.p_banker_bonus_unnatural[i] * payouts.bonus_unnatural[i];
             p_player_lose -= s.p_player_bonus_unnatural[i];
             p_banker_lose -= s.p_banker_bonus_unnatural[i];
+            player_bonus.push((payouts.bonus_unnatural[i], s.p_player_bonus_unnatural[i]));
+            banker_bonus.push((payouts.bonus_unnatural[i], s.p_banker_bonus_unnatural[i]));
         }
         s.ex_player_bonus -= p_player_lose;
         s.ex_banker_bonus -= p_banker_lose;
+        player_bonus.push((-1.0, p_player_lose));
+        banker_bonus.push((-1.0, p_banker_lose));
+        (s.var_player_bonus, s.skew_player_bonus) = get_var_and_skew(&player_bonus);
+        (s.var_banker_bonus, s.skew_banker_bonus) = get_var_and_skew(&banker_bonus);
+
+        // Calculate solution for EZ Baccarat side bets.
+        if let Some(s) = &mut self.sol_ez {
+            s.ex_dragon_7 = s.p_dragon_7 * payouts.dragon_7 - (p_complete - s.p_dragon_7);
+            (s.var_dragon_7, s.skew_dragon_7) = get_var_and_skew(&[
+                (payouts.dragon_7, s.p_dragon_7),
+                (-1.0, p_complete - s.p_dragon_7),
+            ]);
+            s.ex_panda_8 = s.p_panda_8 * payouts.panda_8 - (p_complete - s.p_panda_8);
+            (s.var_panda_8, s.skew_panda_8) = get_var_and_skew(&[
+                (payouts.panda_8, s.p_panda_8),
+                (-1.0, p_complete - s.p_panda_8),
+            ]);
+        }
+
+        // Calculate solution for Super 6.
+        if let (Some(s), Some(payout)) = (&mut self.sol_super_6, payouts.super_6) {
+            s.ex_super_6 = s.p_super_6[0] * payout[0] + s.p_super_6[1] * payout[1]
+                - (p_complete - s.p_super_6[0] - s.p_super_6[1]);
+            (s.var_super_6, s.skew_super_6) = get_var_and_skew(&[
+                (payout[0], s.p_super_6[0]),
+                (payout[1], s.p_super_6[1]),
+                (-1.0, p_complete - s.p_super_6[0] - s.p_super_6[1]),
+            ]);
+        }
+
+        // Calculate solution for Big/Small.
+        if let (Some(s), Some(payout)) = (&mut self.sol_big_small, payouts.big_small) {
+            s.ex_big = s.p_big * payout[0] - s.p_small;
+            (s.var_big, s.skew_big) = get_var_and_skew(&[(payout[0], s.p_big), (-1.0, s.p_small)]);
+            s.ex_small = s.p_small * payout[1] - s.p_big;
+            (s.var_small, s.skew_small) =
+                get_var_and_skew(&[(payout[1], s.p_small), (-1.0, s.p_big)]);
+        }
+
+        // Calculate solution for side bets declared in config.
+        for (s, side_bet) in self.sol_side_bets.iter_mut().zip(&rule.side_bets) {
+            s.ex = 0.0;
+            let mut p_lose = p_complete;
+            let mut distribution = vec![];
+            for (p, outcome) in s.p.iter().zip(&side_bet.outcomes) {
+                s.ex += p * outcome.payout;
+                p_lose -= p;
+                distribution.push((outcome.payout, *p));
+            }
+            s.ex -= p_lose;
+            distribution.push((-1.0, p_lose));
+            (s.var, s.skew) = get_var_and_skew(&distribution);
+        }
+    }
+
+    fn add_p_of_outcome_to_solution(&mut self, outcome: &RoundOutcome, p: f64) {
+        if let Some(s) = &mut self.sol_big_small {
+            if outcome.player_cards + outcome.banker_cards == 4 {
+                s.p_small += p;
+            } else {
+                s.p_big += p;
+            }
+        }
+
+        let (player_sum, banker_sum) = (outcome.player_total, outcome.banker_total);
+        match player_sum.cmp(&banker_sum) {
+            Ordering::Less => {
+                self.sol_main.p_banker_win += p;
+                self.sol_main.p_banker_win_by_total[outcome.banker_cards as usize - 2]
+                    [banker_sum as usize] += p;
+                let delta = (banker_sum - player_sum) as usize;
+                if outcome.is_banker_natural() {
+                    self.sol_bonus.p_banker_bonus_natural_win += p;
+                } else if delta >= 4 {
+                    self.sol_bonus.p_banker_bonus_unnatural[delta - 4] += p;
+                }
+                if let Some(ez) = &mut self.sol_ez {
+                    if outcome.banker_cards == 3 && banker_sum == 7 {
+                        ez.p_dragon_7 += p;
+                    }
+                }
+                if let Some(s) = &mut self.sol_super_6 {
+                    if banker_sum == 6 {
+                        s.p_super_6[outcome.banker_cards as usize - 2] += p;
+                    }
+                }
+            }
+            Ordering::Equal => {
+                self.sol_main.p_tie += p;
+                self.sol_main.p_tie_on_total[player_sum as usize] += p;
+                if outcome.is_player_natural() {
+                    self.sol_bonus.p_bonus_natural_tie += p;
+                }
+            }
+            Ordering::Greater => {
+                self.sol_main.p_player_win += p;
+                let delta = (player_sum - banker_sum) as usize;
+                if outcome.is_player_natural() {
+                    self.sol_bonus.p_player_bonus_natural_win += p;
+                } else if delta >= 4 {
+                    self.sol_bonus.p_player_bonus_unnatural[delta - 4] += p;
+                }
+                if let Some(ez) = &mut self.sol_ez {
+                    if outcome.player_cards == 3 && player_sum == 8 {
+                        ez.p_panda_8 += p;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the variance and skewness of the profit of a bet, given the profit
+/// and probability of each way it's settled. The bet pushes otherwise.
+fn get_var_and_skew(distribution: &[(f64, f64)]) -> (f64, f64) {
+    let moment = |k| -> f64 { distribution.iter().map(|(x, p)| p * x.powi(k)).sum() };
+    let (ex, m2, m3) = (moment(1), moment(2), moment(3));
+    let var = (m2 - ex * ex).max(0.0);
+    if var < 1e-15 {
+        return (0.0, 0.0);
     }
+    let skew = (m3 - 3.0 * ex * var - ex.powi(3)) / var.powf(1.5);
+    (var, skew)
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct SolutionMain {
     pub p_player_win: f64,
     pub ex_player_win: f64,
+    pub var_player_win: f64,
+    pub skew_player_win: f64,
 
     pub p_banker_win: f64,
+    pub p_banker_win_by_total: [[f64; 10]; 2], // [banker_cards - 2][banker_total]
     pub ex_banker_win: f64,
+    pub var_banker_win: f64,
+    pub skew_banker_win: f64,
 
     pub p_tie: f64,
     pub ex_tie: f64,
+    pub var_tie: f64,
+    pub skew_tie: f64,
+
+    pub p_tie_on_total: [f64; 10],
+    pub ex_tie_on_total: Option<[f64; 10]>, // Only available when tie on total is offered.
+    pub var_tie_on_total: Option<[f64; 10]>,
+    pub skew_tie_on_total: Option<[f64; 10]>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct SolutionPair {
     pub p_unsuit_pair: f64,
     pub ex_unsuit_pair: f64,
+    pub var_unsuit_pair: f64,
+    pub skew_unsuit_pair: f64,
 
     pub p_either_pair: f64,
     pub ex_either_pair: f64,
+    pub var_either_pair: f64,
+    pub skew_either_pair: f64,
 
     pub p_suit_pair: [f64; 2],
     pub ex_suit_pair: f64,
+    pub var_suit_pair: f64,
+    pub skew_suit_pair: f64,
+
+    /// Banker Pair, only when it differs from Player Pair (the unsuit pair
+    /// above), i.e. when some cards of the round are known. See
+    /// conditional::calculate.
+    pub p_banker_unsuit_pair: Option<f64>,
+    pub ex_banker_unsuit_pair: Option<f64>,
+    pub var_banker_unsuit_pair: Option<f64>,
+    pub skew_banker_unsuit_pair: Option<f64>,
+
+    /// The joint distribution of player's and banker's initial cards, indexed
+    /// by [player][banker], where 0 is no pair, 1 a pair that isn't perfect,
+    /// and 2 a perfect pair. All pair bets are settled by it.
+    pub p_joint: [[f64; 3]; 3],
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
@@ -223,279 +740,1059 @@ pub struct SolutionBonus {
     pub p_bonus_natural_tie: f64,
 
     pub ex_player_bonus: f64,
+    pub var_player_bonus: f64,
+    pub skew_player_bonus: f64,
     pub ex_banker_bonus: f64,
+    pub var_banker_bonus: f64,
+    pub skew_banker_bonus: f64,
 }
 
-const S: bool = false;
-const D: bool = true;
-pub static RULE_CHART: [[bool; 10]; 10] = [
-    [D, D, D, D, D, D, D, D, D, D],
-    [D, D, D, D, D, D, D, D, D, D],
-    [D, D, D, D, D, D, D, D, D, D],
-    [D, D, D, D, D, D, D, D, S, D],
-    [S, S, D, D, D, D, D, D, S, S],
-    [S, S, S, S, D, D, D, D, S, S],
-    [S, S, S, S, S, S, D, D, S, S],
-    [S, S, S, S, S, S, S, S, S, S],
-    [S, S, S, S, S, S, S, S, S, S],
-    [S, S, S, S, S, S, S, S, S, S],
-];
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SolutionEz {
+    pub p_dragon_7: f64, // Banker wins with a three-card 7.
+    pub ex_dragon_7: f64,
+    pub var_dragon_7: f64,
+    pub skew_dragon_7: f64,
 
-pub mod functional {
-    use super::*;
+    pub p_panda_8: f64, // Player wins with a three-card 8.
+    pub ex_panda_8: f64,
+    pub var_panda_8: f64,
+    pub skew_panda_8: f64,
+}
 
-    pub fn calculate(
-        // Input
-        rule: &Rule,
-        counter: &mut Counter,
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SolutionSuper6 {
+    pub p_super_6: [f64; 2], // Banker wins with a two-card or three-card 6.
+    pub ex_super_6: f64,
+    pub var_super_6: f64,
+    pub skew_super_6: f64,
+}
 
-        // Output
-        solution: &mut Solution,
-    ) {
-        *solution = Default::default();
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SolutionBigSmall {
+    pub p_big: f64, // 5 or 6 cards are dealt.
+    pub ex_big: f64,
+    pub var_big: f64,
+    pub skew_big: f64,
 
-        // Step 1: Calculate probabilities of main bets and bonus bets.
-        let bcr_counter = &mut counter.bcr_value_count;
-        let total_count = counter.total;
-        for p0 in 0..=9 {
-            if bcr_counter[p0] == 0 {
-                continue;
-            }
-            let p = bcr_counter[p0] as f64 / total_count as f64;
-            bcr_counter[p0] -= 1;
-            let total_count = total_count - 1;
+    pub p_small: f64, // 4 cards are dealt.
+    pub ex_small: f64,
+    pub var_small: f64,
+    pub skew_small: f64,
+}
 
-            for p1 in 0..=p0 {
-                if bcr_counter[p1] == 0 {
-                    continue;
-                }
-                let p = p * (bcr_counter[p1] * if p0 == p1 { 1 } else { 2 }) as f64
-                    / total_count as f64;
-                bcr_counter[p1] -= 1;
-                let total_count = total_count - 1;
-                let player_sum = (p0 + p1) % 10;
+/// The game-theoretic optimal strategies in Chemin de fer. The main bets of
+/// the solution are calculated as if both sides play these strategies.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SolutionCheminDeFer {
+    pub p_player_draw: f64,      // Probability that player draws on 5.
+    pub p_banker_draw: Vec<f64>, // Aligned with CHEMIN_DE_FER_BANKER_OPTIONS.
+}
 
-                for b0 in 0..=9 {
-                    if bcr_counter[b0] == 0 {
-                        continue;
-                    }
-                    let p = p * bcr_counter[b0] as f64 / total_count as f64;
-                    bcr_counter[b0] -= 1;
-                    let total_count = total_count - 1;
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SolutionSideBet {
+    pub name: String,
+    pub p: Vec<f64>, // p[i] stands for the probability that outcome i decides the bet.
+    pub ex: f64,
+    pub var: f64,
+    pub skew: f64,
+}
 
-                    for b1 in 0..=b0 {
-                        if bcr_counter[b1] == 0 {
-                            continue;
-                        }
-                        let p = p * (bcr_counter[b1] * if b0 == b1 { 1 } else { 2 }) as f64
-                            / total_count as f64;
-                        bcr_counter[b1] -= 1;
-                        let total_count = total_count - 1;
-                        let banker_sum = (b0 + b1) % 10;
-
-                        // Core logic 1: Check if player or/and banker get(s) a natural.
-                        if player_sum >= 8 || banker_sum >= 8 {
-                            match player_sum.cmp(&banker_sum) {
-                                Ordering::Less => {
-                                    solution.sol_main.p_banker_win += p;
-                                    solution.sol_bonus.p_banker_bonus_natural_win += p;
-                                }
-                                Ordering::Equal => {
-                                    solution.sol_main.p_tie += p;
-                                    solution.sol_bonus.p_bonus_natural_tie += p;
-                                }
-                                Ordering::Greater => {
-                                    solution.sol_main.p_player_win += p;
-                                    solution.sol_bonus.p_player_bonus_natural_win += p;
-                                }
-                            }
-                        }
-                        // Core logic 2: If player's initial sum is 6 or 7, then no extra
-                        // card is dealt to player.
-                        else if player_sum >= 6 {
-                            // When player doesn't draw a card, at the same time banker's
-                            // initial sum is also 6 or 7, then banker doesn't draw a
-                            // card either. Otherwise banker draws a card.
-                            if banker_sum >= 6 {
-                                add_p_of_unnatural_to_solution(player_sum, banker_sum, solution, p);
-                            } else {
-                                deal_final_banker_card_loop(
-                                    bcr_counter,
-                                    player_sum,
-                                    banker_sum,
-                                    p,
-                                    total_count,
-                                    solution,
-                                );
-                            }
-                        }
-                        // Core logic 3: If player's initial sum <= 5, then player draws
-                        // an extra card. Then whether banker draws or stands depends on
-                        // her initial sum and the extra card drawn by player.
-                        else {
-                            for player_extra_card in 0..=9 {
-                                if bcr_counter[player_extra_card] == 0 {
-                                    continue;
-                                }
-                                let p =
-                                    p * bcr_counter[player_extra_card] as f64 / total_count as f64;
-                                let total_count = total_count - 1;
-                                bcr_counter[player_extra_card] -= 1;
-                                let player_sum = (player_sum + player_extra_card) % 10;
-
-                                // Core logic 4: Check if banker should draw an extra card.
-                                if RULE_CHART[banker_sum][player_extra_card] {
-                                    deal_final_banker_card_loop(
-                                        bcr_counter,
-                                        player_sum,
-                                        banker_sum,
-                                        p,
-                                        total_count,
-                                        solution,
-                                    );
-                                } else {
-                                    add_p_of_unnatural_to_solution(
-                                        player_sum, banker_sum, solution, p,
-                                    );
-                                }
+/// The joint distribution of the final outcome of a round, i.e., the final
+/// totals of player and banker and the number of cards each of them has.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OutcomeDistribution {
+    /// Indexed by [player_cards - 2][banker_cards - 2][player_total][banker_total].
+    pub p: [[[[f64; 10]; 10]; 2]; 2],
 
-                                bcr_counter[player_extra_card] += 1;
-                            }
-                        }
+    /// Probability that the shoe runs out of cards before the round completes.
+    pub p_incomplete: f64,
+}
 
-                        bcr_counter[b1] += 1;
-                    }
-                    bcr_counter[b0] += 1;
-                }
-                bcr_counter[p1] += 1;
-            }
-            bcr_counter[p0] += 1;
-        }
+impl OutcomeDistribution {
+    fn merge(&mut self, other: &Self) {
+        self.merge_scaled(other, 1.0);
+    }
 
-        // Step 2: Calculate probabilities of pair bets.
-        let total_pairs = (counter.total * (counter.total - 1)) as f64;
-        for count in counter.value_count {
-            solution.sol_pair.p_unsuit_pair += (count * count.wrapping_sub(1)) as f64 / total_pairs;
+    /// Adds the probabilities of the other distribution times the scale.
+    fn merge_scaled(&mut self, other: &Self, scale: f64) {
+        for (p, q) in self
+            .p
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .zip(other.p.iter().flatten().flatten())
+        {
+            for (p, q) in p.iter_mut().zip(q) {
+                *p += q * scale;
+            }
         }
+        self.p_incomplete += other.p_incomplete * scale;
+    }
 
-        let total_quads = {
-            let tot = counter.total as u128;
-            tot * (tot - 1) * (tot - 2) * (tot - 3)
-        } as f64;
-        let either_pair = {
-            let mut num1 = 0u128;
-            let mut num2 = 0u128;
-            for i in 0..13 {
-                let count1 = counter.value_count[i] as u128;
-                if count1 <= 1 {
-                    continue;
-                }
-                let res = count1 * (count1 - 1);
-                counter.value_count[i] -= 2;
-
-                for j in 0..13 {
-                    let count2 = counter.value_count[j] as u128;
-                    if count2 <= 1 {
-                        continue;
-                    }
-                    let res1 = res * count2 * (counter.total - 2 - counter.value_count[j]) as u128;
-                    num1 += res1;
-                    let res2 = res * count2 * (count2 - 1);
-                    num2 += res2;
-                }
+    fn add(&mut self, outcome: RoundOutcome, p: f64) {
+        self.p[outcome.player_cards as usize - 2][outcome.banker_cards as usize - 2]
+            [outcome.player_total as usize][outcome.banker_total as usize] += p;
+    }
 
-                counter.value_count[i] += 2;
-            }
-            num1 * 2 + num2
-        };
-        solution.sol_pair.p_either_pair = either_pair as f64 / total_quads;
+    pub fn get_p(&self, outcome: &RoundOutcome) -> f64 {
+        self.p[outcome.player_cards as usize - 2][outcome.banker_cards as usize - 2]
+            [outcome.player_total as usize][outcome.banker_total as usize]
+    }
 
-        let card_count = &mut counter.card_count;
-        for i in 0..52 {
-            if card_count[i] <= 1 {
-                continue;
-            }
-            let p_first_pair =
-                (card_count[i] * (card_count[i].wrapping_sub(1))) as f64 / total_pairs;
-            card_count[i] -= 2;
-            let mut p_second_pair = 0.0;
-            let total_pairs = ((counter.total - 2) * (counter.total - 3)) as f64;
-            for count in card_count.iter() {
-                p_second_pair += (*count * (count.wrapping_sub(1))) as f64 / total_pairs;
-            }
-            card_count[i] += 2;
+    /// Returns the probability that the final outcome satisfies the predicate.
+    pub fn get_p_where<F: Fn(&RoundOutcome) -> bool>(&self, predicate: F) -> f64 {
+        self.iter()
+            .filter(|(outcome, _)| predicate(outcome))
+            .map(|(_, p)| p)
+            .sum()
+    }
 
-            solution.sol_pair.p_suit_pair[0] += p_first_pair * (1.0 - p_second_pair);
-            solution.sol_pair.p_suit_pair[1] += p_first_pair * p_second_pair;
+    /// Returns the probabilities that the round deals 4, 5 or 6 cards
+    /// respectively.
+    pub fn get_p_cards(&self) -> [f64; 3] {
+        let mut p_cards = [0.0; 3];
+        for (outcome, p) in self.iter() {
+            p_cards[(outcome.player_cards + outcome.banker_cards - 4) as usize] += p;
         }
-        solution.sol_pair.p_suit_pair[0] *= 2.0;
+        p_cards
+    }
 
-        // Step 3: Calculate expectations.
-        solution.calculate_ex_based_on_p(rule);
+    /// Returns the expectation of a bet, given the profit of 1 unit bet in
+    /// each final outcome.
+    pub fn get_ex<F: Fn(&RoundOutcome) -> f64>(&self, profit: F) -> f64 {
+        self.iter().map(|(outcome, p)| p * profit(&outcome)).sum()
     }
 
-    fn deal_final_banker_card_loop(
-        bcr_counter: &[u32; 10],
-        player_sum: usize,
-        banker_sum: usize,
-        p: f64,
-        total_count: u32,
+    /// Iterates over all final outcomes (including impossible ones, whose
+    /// probability is 0) and their probabilities.
+    pub fn iter(&self) -> impl Iterator<Item = (RoundOutcome, f64)> + '_ {
+        self.p.iter().enumerate().flat_map(|(pc, p)| {
+            p.iter().enumerate().flat_map(move |(bc, p)| {
+                p.iter().enumerate().flat_map(move |(pt, p)| {
+                    p.iter().enumerate().map(move |(bt, p)| {
+                        let outcome = RoundOutcome {
+                            player_total: pt as u8,
+                            banker_total: bt as u8,
+                            player_cards: pc as u8 + 2,
+                            banker_cards: bc as u8 + 2,
+                        };
+                        (outcome, *p)
+                    })
+                })
+            })
+        })
+    }
+}
+
+/// The counters with more of a card than these many decks can hold are
+/// rejected, which keeps the products of the counts in range.
+pub const MAX_DECKS: u32 = 1000;
+
+/// At least 4 and at most 6 cards are dealt in a round.
+pub const MIN_CARDS_PER_ROUND: u32 = 4;
+pub const MAX_CARDS_PER_ROUND: u32 = 6;
+
+/// Returns the expected number of rounds that start before the cut card is
+/// reached, i.e. the rounds left in the shoe, if cards_before_cut more cards
+/// are to be dealt until the cut card. Every round is assumed to deal 4, 5 or 6
+/// cards with the probabilities p_cards (e.g. Solution::p_cards of the next
+/// round), which are normalized.
+pub fn get_expected_rounds_remaining(p_cards: &[f64; 3], cards_before_cut: u32) -> f64 {
+    let total: f64 = p_cards.iter().sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    // rounds[d] is the expected rounds left with d cards before the cut card.
+    let mut rounds = vec![0.0; cards_before_cut as usize + 1];
+    for d in 1..rounds.len() {
+        rounds[d] = 1.0;
+        for (cards, p) in (MIN_CARDS_PER_ROUND as usize..).zip(p_cards) {
+            rounds[d] += p / total * rounds[d.saturating_sub(cards)];
+        }
+    }
+    rounds[cards_before_cut as usize]
+}
+
+/// The initial total of player on which player may either draw or stand in
+/// Chemin de fer.
+pub const CHEMIN_DE_FER_PLAYER_OPTION: u8 = 5;
+
+/// The cells (banker's initial total, player's third card) of the tableau in
+/// which banker may either draw or stand in Chemin de fer.
+pub static CHEMIN_DE_FER_BANKER_OPTIONS: [(u8, u8); 2] = [(3, 9), (5, 4)];
+
+/// The probabilities that player and banker draw the third card in each
+/// situation. They're either 0 or 1 when following the tableau, while player and banker
+/// may play their options with mixed strategies in Chemin de fer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DrawingStrategy {
+    /// Indexed by player's initial total.
+    pub player: [f64; 10],
+    /// Indexed by [banker's initial total][player's third card].
+    pub banker: [[f64; 10]; 10],
+    /// Indexed by banker's initial total.
+    pub banker_when_player_stands: [f64; 10],
+}
+
+impl DrawingStrategy {
+    /// Both sides draw exactly as the tableau says.
+    pub fn from_tableau(tableau: &Tableau) -> Self {
+        let to_p = |draw: bool| if draw { 1.0 } else { 0.0 };
+        let mut strategy = Self {
+            player: tableau.player.0.map(to_p),
+            banker: [[0.0; 10]; 10],
+            banker_when_player_stands: tableau.banker_when_player_stands.0.map(to_p),
+        };
+        for (p, row) in strategy.banker.iter_mut().zip(&tableau.banker) {
+            *p = row.0.map(to_p);
+        }
+        strategy
+    }
+
+    /// p_banker_draw[i] stands for the probability that banker draws on
+    /// CHEMIN_DE_FER_BANKER_OPTIONS[i].
+    pub fn chemin_de_fer(tableau: &Tableau, p_player_draw: f64, p_banker_draw: &[f64]) -> Self {
+        let mut strategy = Self::from_tableau(tableau);
+        strategy.player[CHEMIN_DE_FER_PLAYER_OPTION as usize] = p_player_draw;
+        for (&(banker_total, player_third), &p) in
+            CHEMIN_DE_FER_BANKER_OPTIONS.iter().zip(p_banker_draw)
+        {
+            strategy.banker[banker_total as usize][player_third as usize] = p;
+        }
+        strategy
+    }
+}
+
+pub mod functional {
+    use super::*;
+    use rayon::prelude::*;
+
+    pub fn calculate(
+        // Input
+        rule: &Rule,
+        counter: &mut Counter,
+
+        // Output
         solution: &mut Solution,
     ) {
-        for card in 0..=9 {
-            if bcr_counter[card] == 0 {
+        let mut outcomes = OutcomeDistribution::default();
+        calculate_with_outcomes(rule, counter, solution, &mut outcomes);
+    }
+
+    /// Returns an error if there aren't enough cards to even start a round,
+    /// or a count is more than MAX_DECKS decks can hold. Note that with fewer
+    /// than MAX_CARDS_PER_ROUND cards, a round may still run out of cards,
+    /// whose probability is given by Solution::p_incomplete.
+    pub fn check_counter(counter: &Counter) -> Result<(), Error> {
+        check_card_count(&counter.card_count)?;
+        if counter.total < MIN_CARDS_PER_ROUND {
+            return Err(Error::NotEnoughCards(counter.total));
+        }
+        Ok(())
+    }
+
+    pub fn try_calculate(
+        // Input
+        rule: &Rule,
+        counter: &mut Counter,
+
+        // Output
+        solution: &mut Solution,
+    ) -> Result<(), Error> {
+        check_counter(counter)?;
+        calculate(rule, counter, solution);
+        Ok(())
+    }
+
+    pub fn try_calculate_with_outcomes(
+        // Input
+        rule: &Rule,
+        counter: &mut Counter,
+
+        // Output
+        solution: &mut Solution,
+        outcomes: &mut OutcomeDistribution,
+    ) -> Result<(), Error> {
+        check_counter(counter)?;
+        calculate_with_outcomes(rule, counter, solution, outcomes);
+        Ok(())
+    }
+
+    pub fn calculate_with_outcomes(
+        // Input
+        rule: &Rule,
+        counter: &mut Counter,
+
+        // Output
+        solution: &mut Solution,
+        outcomes: &mut OutcomeDistribution,
+    ) {
+        calculate_with_outcomes_impl(rule, counter, solution, outcomes, false);
+    }
+
+    /// The same as calculate(), but enumerates the rounds in parallel.
+    pub fn par_calculate(
+        // Input
+        rule: &Rule,
+        counter: &mut Counter,
+
+        // Output
+        solution: &mut Solution,
+    ) {
+        let mut outcomes = OutcomeDistribution::default();
+        par_calculate_with_outcomes(rule, counter, solution, &mut outcomes);
+    }
+
+    /// The same as calculate_with_outcomes(), but enumerates the rounds in
+    /// parallel.
+    pub fn par_calculate_with_outcomes(
+        // Input
+        rule: &Rule,
+        counter: &mut Counter,
+
+        // Output
+        solution: &mut Solution,
+        outcomes: &mut OutcomeDistribution,
+    ) {
+        calculate_with_outcomes_impl(rule, counter, solution, outcomes, true);
+    }
+
+    fn calculate_with_outcomes_impl(
+        rule: &Rule,
+        counter: &mut Counter,
+        solution: &mut Solution,
+        outcomes: &mut OutcomeDistribution,
+        parallel: bool,
+    ) {
+        *solution = Default::default();
+
+        // Step 1: Calculate probabilities of each final outcome of the round.
+        if rule.chemin_de_fer {
+            let sol = solve_chemin_de_fer(rule, counter, outcomes, parallel);
+            let strategy = DrawingStrategy::chemin_de_fer(
+                &rule.tableau,
+                sol.p_player_draw,
+                &sol.p_banker_draw,
+            );
+            calculate_outcomes(counter, &strategy, outcomes, parallel);
+            solution.sol_chemin_de_fer = Some(sol);
+        } else {
+            let strategy = DrawingStrategy::from_tableau(&rule.tableau);
+            calculate_outcomes(counter, &strategy, outcomes, parallel);
+        }
+
+        calculate_based_on_outcomes(rule, counter, outcomes, solution);
+    }
+
+    /// Steps 2 to 4 of the calculation, given the final outcomes of the round.
+    pub(crate) fn calculate_based_on_outcomes(
+        // Input
+        rule: &Rule,
+        counter: &mut Counter,
+        outcomes: &OutcomeDistribution,
+
+        // Output
+        solution: &mut Solution,
+    ) {
+        // Step 2: Calculate probabilities of main bets, bonus bets and side bets
+        // declared in config based on the outcomes.
+        solution.p_incomplete = outcomes.p_incomplete;
+        solution.p_cards = outcomes.get_p_cards();
+        solution.outcomes = outcomes.clone();
+        if rule.ez_baccarat {
+            solution.sol_ez = Some(Default::default());
+        }
+        if rule.payouts.super_6.is_some() {
+            solution.sol_super_6 = Some(Default::default());
+        }
+        if rule.payouts.big_small.is_some() {
+            solution.sol_big_small = Some(Default::default());
+        }
+        solution.sol_side_bets = rule
+            .side_bets
+            .iter()
+            .map(|side_bet| SolutionSideBet {
+                name: side_bet.name.clone(),
+                p: vec![0.0; side_bet.outcomes.len()],
+                ..Default::default()
+            })
+            .collect();
+        for (outcome, p) in outcomes.iter() {
+            if p == 0.0 {
                 continue;
             }
-            let p = p * bcr_counter[card] as f64 / total_count as f64;
-            let banker_sum = (banker_sum + card) % 10;
-            add_p_of_unnatural_to_solution(player_sum, banker_sum, solution, p);
+            solution.add_p_of_outcome_to_solution(&outcome, p);
+            for (s, side_bet) in solution.sol_side_bets.iter_mut().zip(&rule.side_bets) {
+                if let Some(i) = side_bet.settle(&outcome) {
+                    s.p[i] += p;
+                }
+            }
+        }
+
+        // Step 3: Calculate probabilities of pair bets, and how they go with
+        // the final outcomes. They're all void if the first four cards cannot
+        // be dealt.
+        if counter.total >= MIN_CARDS_PER_ROUND {
+            calculate_pair_bets(counter, solution);
+            let strategy = match &solution.sol_chemin_de_fer {
+                Some(sol) => DrawingStrategy::chemin_de_fer(
+                    &rule.tableau,
+                    sol.p_player_draw,
+                    &sol.p_banker_draw,
+                ),
+                None => DrawingStrategy::from_tableau(&rule.tableau),
+            };
+            solution.pair_outcomes =
+                calculate_pair_outcomes(counter, &strategy, outcomes, &solution.sol_pair.p_joint);
+        }
+
+        // Step 4: Calculate expectations.
+        solution.calculate_ex_based_on_p(rule);
+        if counter.total < MIN_CARDS_PER_ROUND {
+            solution.sol_pair = Default::default();
+        }
+    }
+
+    fn calculate_pair_bets(
+        // Input
+        counter: &mut Counter,
+
+        // Output
+        solution: &mut Solution,
+    ) {
+        let total = counter.total as f64;
+        let total_pairs = total * (total - 1.0);
+        for count in counter.value_count {
+            let count = count as f64;
+            solution.sol_pair.p_unsuit_pair += count * (count - 1.0).max(0.0) / total_pairs;
         }
+
+        let total_quads = {
+            let tot = counter.total as u128;
+            tot * (tot - 1) * (tot - 2) * (tot - 3)
+        } as f64;
+        let either_pair = {
+            let mut num1 = 0u128;
+            let mut num2 = 0u128;
+            for i in 0..13 {
+                let count1 = counter.value_count[i] as u128;
+                if count1 <= 1 {
+                    continue;
+                }
+                let res = count1 * (count1 - 1);
+                counter.value_count[i] -= 2;
+
+                for j in 0..13 {
+                    let count2 = counter.value_count[j] as u128;
+                    if count2 == 0 {
+                        continue;
+                    }
+                    let res1 = res * count2 * (counter.total - 2 - counter.value_count[j]) as u128;
+                    num1 += res1;
+                    let res2 = res * count2 * (count2 - 1);
+                    num2 += res2;
+                }
+
+                counter.value_count[i] += 2;
+            }
+            num1 * 2 + num2
+        };
+        solution.sol_pair.p_either_pair = either_pair as f64 / total_quads;
+
+        let card_count = &mut counter.card_count;
+        for i in 0..52 {
+            if card_count[i] <= 1 {
+                continue;
+            }
+            let count = card_count[i] as f64;
+            let p_first_pair = count * (count - 1.0) / total_pairs;
+            card_count[i] -= 2;
+            let mut p_second_pair = 0.0;
+            let total_pairs = (total - 2.0) * (total - 3.0);
+            for count in card_count.iter() {
+                let count = *count as f64;
+                p_second_pair += count * (count - 1.0).max(0.0) / total_pairs;
+            }
+            card_count[i] += 2;
+
+            solution.sol_pair.p_suit_pair[0] += p_first_pair * (1.0 - p_second_pair);
+            solution.sol_pair.p_suit_pair[1] += p_first_pair * p_second_pair;
+        }
+        solution.sol_pair.p_suit_pair[0] *= 2.0;
+
+        solution.sol_pair.p_joint = get_joint_pairs(counter);
+    }
+
+    /// See SolutionPair::p_joint. The counter must have at least 4 cards.
+    fn get_joint_pairs(counter: &mut Counter) -> [[f64; 3]; 3] {
+        let total = counter.total as f64;
+        let first_pairs = total * (total - 1.0);
+        let second_pairs = (total - 2.0) * (total - 3.0);
+
+        // Enumerate player's pairs, and banker's pairs after each.
+        let mut joint = [[0.0; 3]; 3];
+        let mut p_player = [0.0; 3];
+        for i in 0..52 {
+            let count = counter.card_count[i];
+            if count == 0 {
+                continue;
+            }
+            for j in (0..4).map(|suit| suit * 13 + i % 13) {
+                let other_count = counter.card_count[j] - (i == j) as u32;
+                if other_count == 0 {
+                    continue;
+                }
+                let p = count as f64 * other_count as f64 / first_pairs;
+                let player = if i == j { 2 } else { 1 };
+                p_player[player] += p;
+
+                let (first, second) = (Card::from_index(i), Card::from_index(j));
+                counter.remove_card(first);
+                counter.remove_card(second);
+                let pairs: u64 = counter
+                    .value_count
+                    .iter()
+                    .map(|&c| c as u64 * c.saturating_sub(1) as u64)
+                    .sum();
+                let perfect: u64 = counter
+                    .card_count
+                    .iter()
+                    .map(|&c| c as u64 * c.saturating_sub(1) as u64)
+                    .sum();
+                joint[player][1] += p * (pairs - perfect) as f64 / second_pairs;
+                joint[player][2] += p * perfect as f64 / second_pairs;
+                counter.add_card(first);
+                counter.add_card(second);
+            }
+        }
+
+        // Banker's initial cards are distributed the same as player's.
+        for k in 1..3 {
+            joint[0][k] = p_player[k] - joint[1][k] - joint[2][k];
+            joint[k][0] = p_player[k] - joint[k][1] - joint[k][2];
+        }
+        joint[0][0] = 1.0 - joint.iter().flatten().sum::<f64>();
+        joint
     }
 
-    fn add_p_of_unnatural_to_solution(
+    /// Calculates the distribution of the final outcomes of a round, where
+    /// both hands draw according to the given (possibly mixed) strategy.
+    pub fn calculate_outcomes_with_strategy(
+        // Input
+        counter: &mut Counter,
+        strategy: &DrawingStrategy,
+
+        // Output
+        outcomes: &mut OutcomeDistribution,
+    ) {
+        calculate_outcomes(counter, strategy, outcomes, false);
+    }
+
+    fn calculate_outcomes(
+        counter: &mut Counter,
+        strategy: &DrawingStrategy,
+        outcomes: &mut OutcomeDistribution,
+        parallel: bool,
+    ) {
+        *outcomes = Default::default();
+
+        // Only the rounds that complete are enumerated. The rest is the
+        // probability that the shoe runs out of cards in the middle.
+        enumerate_outcomes(
+            &mut counter.bcr_value_count,
+            counter.total,
+            strategy,
+            outcomes,
+            parallel,
+        );
+        if counter.total < MAX_CARDS_PER_ROUND {
+            outcomes.p_incomplete = (1.0 - outcomes.iter().map(|(_, p)| p).sum::<f64>()).max(0.0);
+        }
+    }
+
+    fn enumerate_outcomes(
+        bcr_counter: &mut [u32; 10],
+        total_count: u32,
+        strategy: &DrawingStrategy,
+        outcomes: &mut OutcomeDistribution,
+        parallel: bool,
+    ) {
+        if parallel {
+            let bcr_counter = *bcr_counter;
+            *outcomes = (0..=9)
+                .into_par_iter()
+                .map(|p0| {
+                    let mut bcr_counter = bcr_counter;
+                    let mut outcomes = OutcomeDistribution::default();
+                    enumerate_outcomes_with_first_card(
+                        &mut bcr_counter,
+                        total_count,
+                        p0,
+                        strategy,
+                        &mut outcomes,
+                    );
+                    outcomes
+                })
+                .reduce(OutcomeDistribution::default, |mut sum, outcomes| {
+                    sum.merge(&outcomes);
+                    sum
+                });
+        } else {
+            for p0 in 0..=9 {
+                enumerate_outcomes_with_first_card(
+                    bcr_counter,
+                    total_count,
+                    p0,
+                    strategy,
+                    outcomes,
+                );
+            }
+        }
+    }
+
+    /// Enumerates the rounds where player's first card is p0.
+    fn enumerate_outcomes_with_first_card(
+        bcr_counter: &mut [u32; 10],
+        total_count: u32,
+        p0: usize,
+        strategy: &DrawingStrategy,
+        outcomes: &mut OutcomeDistribution,
+    ) {
+        if bcr_counter[p0] == 0 {
+            return;
+        }
+        let p = bcr_counter[p0] as f64 / total_count as f64;
+        bcr_counter[p0] -= 1;
+        let total_count = total_count - 1;
+
+        for p1 in 0..=p0 {
+            if bcr_counter[p1] == 0 {
+                continue;
+            }
+            let p =
+                p * (bcr_counter[p1] * if p0 == p1 { 1 } else { 2 }) as f64 / total_count as f64;
+            bcr_counter[p1] -= 1;
+            let total_count = total_count - 1;
+            let player_sum = (p0 + p1) % 10;
+
+            for b0 in 0..=9 {
+                if bcr_counter[b0] == 0 {
+                    continue;
+                }
+                let p = p * bcr_counter[b0] as f64 / total_count as f64;
+                bcr_counter[b0] -= 1;
+                let total_count = total_count - 1;
+
+                for b1 in 0..=b0 {
+                    if bcr_counter[b1] == 0 {
+                        continue;
+                    }
+                    let p = p * (bcr_counter[b1] * if b0 == b1 { 1 } else { 2 }) as f64
+                        / total_count as f64;
+                    bcr_counter[b1] -= 1;
+                    let total_count = total_count - 1;
+                    let banker_sum = (b0 + b1) % 10;
+                    deal_third_cards(
+                        bcr_counter,
+                        total_count,
+                        player_sum,
+                        banker_sum,
+                        strategy,
+                        outcomes,
+                        p,
+                    );
+                    bcr_counter[b1] += 1;
+                }
+                bcr_counter[b0] += 1;
+            }
+            bcr_counter[p1] += 1;
+        }
+        bcr_counter[p0] += 1;
+    }
+
+    /// Deals the third cards after the initial cards, which are dealt with
+    /// probability p and leave bcr_counter, and adds the final outcomes.
+    fn deal_third_cards(
+        bcr_counter: &mut [u32; 10],
+        total_count: u32,
         player_sum: usize,
         banker_sum: usize,
-        solution: &mut Solution,
+        strategy: &DrawingStrategy,
+        outcomes: &mut OutcomeDistribution,
         p: f64,
     ) {
-        match player_sum.cmp(&banker_sum) {
-            Ordering::Less => {
-                solution.sol_main.p_banker_win += p;
-                let delta = banker_sum - player_sum;
-                if delta >= 4 {
-                    solution.sol_bonus.p_banker_bonus_unnatural[delta - 4] += p;
+        // Core logic 1: Check if player or/and banker get(s) a natural.
+        if player_sum >= 8 || banker_sum >= 8 {
+            add_p_of_outcome(player_sum, banker_sum, 2, 2, outcomes, p);
+            return;
+        }
+
+        // Core logic 2: Player stands. Whether banker draws or
+        // stands depends on her initial sum only.
+        let p_player_draw = strategy.player[player_sum];
+        if p_player_draw < 1.0 {
+            let p = p * (1.0 - p_player_draw);
+            let p_banker_draw = strategy.banker_when_player_stands[banker_sum];
+            if p_banker_draw < 1.0 {
+                let p = p * (1.0 - p_banker_draw);
+                add_p_of_outcome(player_sum, banker_sum, 2, 2, outcomes, p);
+            }
+            if p_banker_draw > 0.0 {
+                deal_final_banker_card_loop(
+                    bcr_counter,
+                    player_sum,
+                    banker_sum,
+                    2,
+                    p * p_banker_draw,
+                    total_count,
+                    outcomes,
+                );
+            }
+        }
+
+        // Core logic 3: Player draws an extra card. Then whether
+        // banker draws or stands depends on her initial sum and the
+        // extra card drawn by player.
+        if p_player_draw > 0.0 {
+            let p = p * p_player_draw;
+            for player_extra_card in 0..=9 {
+                if bcr_counter[player_extra_card] == 0 {
+                    continue;
+                }
+                let p = p * bcr_counter[player_extra_card] as f64 / total_count as f64;
+                let total_count = total_count - 1;
+                bcr_counter[player_extra_card] -= 1;
+                let player_sum = (player_sum + player_extra_card) % 10;
+
+                // Core logic 4: Check if banker should draw an extra card.
+                let p_banker_draw = strategy.banker[banker_sum][player_extra_card];
+                if p_banker_draw < 1.0 {
+                    let p = p * (1.0 - p_banker_draw);
+                    add_p_of_outcome(player_sum, banker_sum, 3, 2, outcomes, p);
                 }
+                if p_banker_draw > 0.0 {
+                    deal_final_banker_card_loop(
+                        bcr_counter,
+                        player_sum,
+                        banker_sum,
+                        3,
+                        p * p_banker_draw,
+                        total_count,
+                        outcomes,
+                    );
+                }
+
+                bcr_counter[player_extra_card] += 1;
             }
-            Ordering::Equal => {
-                solution.sol_main.p_tie += p;
+        }
+    }
+
+    /// See Solution::pair_outcomes. A hand can only have a pair if its initial
+    /// cards have the same value, and which cards of that value they are
+    /// doesn't change how the round goes on. So only the rounds where either
+    /// hand's initial cards have the same value are enumerated by value, and
+    /// split by the pairs they may have. The rounds without pairs are what's
+    /// left of the outcomes. The counter must have at least 4 cards.
+    fn calculate_pair_outcomes(
+        counter: &mut Counter,
+        strategy: &DrawingStrategy,
+        outcomes: &OutcomeDistribution,
+        p_joint: &[[f64; 3]; 3],
+    ) -> [[OutcomeDistribution; 3]; 3] {
+        // The pairs of a hand given the value of its initial cards. Whatever
+        // else is dealt, both cards are equally likely to be any two of that
+        // value in the shoe.
+        let p_hand: Vec<[f64; 3]> = (0..10)
+            .map(|v| {
+                let weights = get_hand_pair_weights(counter, v);
+                let total: f64 = weights.iter().sum();
+                weights.map(|w| if total > 0.0 { w / total } else { 0.0 })
+            })
+            .collect();
+
+        // The rounds are added up by the value of the hand that may pair, and
+        // split by the pairs only at the end, which saves enumerating them
+        // once for each pair.
+        let mut pair_outcomes: [[OutcomeDistribution; 3]; 3] = Default::default();
+        let mut player_only = vec![OutcomeDistribution::default(); 10];
+        let mut banker_only = vec![OutcomeDistribution::default(); 10];
+        // By player's value and banker's pair, when their values differ.
+        let mut both = vec![<[OutcomeDistribution; 3]>::default(); 10];
+        let bcr_counter = &mut counter.bcr_value_count.clone();
+        let total_count = counter.total;
+        for p0 in 0..=9 {
+            for p1 in 0..=p0 {
+                for b0 in 0..=9 {
+                    for b1 in 0..=b0 {
+                        if p0 != p1 && b0 != b1 {
+                            continue;
+                        }
+                        let mut p = 1.0;
+                        for (i, &v) in [p0, p1, b0, b1].iter().enumerate() {
+                            p *= bcr_counter[v] as f64 / (total_count - i as u32) as f64;
+                            bcr_counter[v] = bcr_counter[v].saturating_sub(1);
+                        }
+                        p *= (1 + (p0 != p1) as u32) as f64 * (1 + (b0 != b1) as u32) as f64;
+                        let mut deal = |outcomes: &mut OutcomeDistribution, p: f64| {
+                            if p > 0.0 {
+                                deal_third_cards(
+                                    bcr_counter,
+                                    total_count - 4,
+                                    (p0 + p1) % 10,
+                                    (b0 + b1) % 10,
+                                    strategy,
+                                    outcomes,
+                                    p,
+                                );
+                            }
+                        };
+                        if p0 != p1 {
+                            deal(&mut banker_only[b0], p);
+                        } else if b0 != b1 {
+                            deal(&mut player_only[p0], p);
+                        } else if p0 != b0 {
+                            for (outcomes, q) in both[p0].iter_mut().zip(p_hand[b0]) {
+                                deal(outcomes, p * q);
+                            }
+                        } else if p > 0.0 {
+                            let joint = get_same_value_pair_weights(counter, p0);
+                            let total: f64 = joint.iter().flatten().sum();
+                            for (row, weights) in pair_outcomes.iter_mut().zip(joint) {
+                                for (outcomes, w) in row.iter_mut().zip(weights) {
+                                    deal(outcomes, p * w / total);
+                                }
+                            }
+                        }
+                        *bcr_counter = counter.bcr_value_count;
+                    }
+                }
             }
-            Ordering::Greater => {
-                solution.sol_main.p_player_win += p;
-                let delta = player_sum - banker_sum;
-                if delta >= 4 {
-                    solution.sol_bonus.p_player_bonus_unnatural[delta - 4] += p;
+        }
+
+        for v in 0..10 {
+            for k in 1..3 {
+                pair_outcomes[k][0].merge_scaled(&player_only[v], p_hand[v][k]);
+                pair_outcomes[0][k].merge_scaled(&banker_only[v], p_hand[v][k]);
+            }
+            for (row, &q) in pair_outcomes.iter_mut().zip(&p_hand[v]) {
+                for (outcomes, both) in row.iter_mut().zip(&both[v]) {
+                    outcomes.merge_scaled(both, q);
                 }
             }
         }
+
+        // Whatever has no pair, of which pair_outcomes[0][0] only has some so
+        // far.
+        let mut no_pairs = outcomes.clone();
+        no_pairs.p_incomplete = 0.0;
+        for sub in pair_outcomes.iter().flatten().skip(1) {
+            no_pairs.merge_scaled(sub, -1.0);
+        }
+        for p in no_pairs.p.iter_mut().flatten().flatten().flatten() {
+            *p = p.max(0.0);
+        }
+        pair_outcomes[0][0] = no_pairs;
+
+        // The rounds that run out of cards after the initial ones.
+        for (sub, &p) in pair_outcomes
+            .iter_mut()
+            .flatten()
+            .zip(p_joint.iter().flatten())
+        {
+            let p_complete: f64 = sub.iter().map(|(_, p)| p).sum();
+            sub.p_incomplete = (p - p_complete).max(0.0);
+        }
+        pair_outcomes
+    }
+
+    /// The number of ordered ways to deal two cards of the value v that are
+    /// no pair, a pair that isn't perfect, and a perfect pair respectively.
+    fn get_hand_pair_weights(counter: &Counter, v: usize) -> [f64; 3] {
+        let pairs = |count: u32| count as f64 * count.saturating_sub(1) as f64;
+        let (mut count, mut pair, mut perfect) = (0, 0.0, 0.0);
+        for rank in (0..13).filter(|&rank| Card::from_index(rank).to_bcr_value_index() == v) {
+            count += counter.value_count[rank];
+            pair += pairs(counter.value_count[rank]);
+            perfect += (0..4)
+                .map(|suit| pairs(counter.card_count[suit * 13 + rank]))
+                .sum::<f64>();
+        }
+        [pairs(count) - pair, pair - perfect, perfect]
+    }
+
+    /// The number of ordered ways to deal the initial cards of both hands,
+    /// all of the value v, by the pairs of player and banker.
+    fn get_same_value_pair_weights(counter: &mut Counter, v: usize) -> [[f64; 3]; 3] {
+        let cards: Vec<Card> = (0..52)
+            .map(Card::from_index)
+            .filter(|card| card.to_bcr_value_index() == v)
+            .collect();
+        let mut joint = [[0.0; 3]; 3];
+        for &first in &cards {
+            let count = counter.card_count[first.to_index()];
+            if count == 0 {
+                continue;
+            }
+            counter.remove_card(first);
+            for &second in &cards {
+                let other_count = counter.card_count[second.to_index()];
+                if other_count == 0 {
+                    continue;
+                }
+                counter.remove_card(second);
+                let player = (first.value == second.value) as usize + (first == second) as usize;
+                let banker = get_hand_pair_weights(counter, v);
+                for (p, w) in joint[player].iter_mut().zip(banker) {
+                    *p += count as f64 * other_count as f64 * w;
+                }
+                counter.add_card(second);
+            }
+            counter.add_card(first);
+        }
+        joint
+    }
+
+    /// Finds the game-theoretic optimal strategies of player and banker on
+    /// their options in Chemin de fer, where banker tries to minimize the
+    /// expectation of "Player Win".
+    fn solve_chemin_de_fer(
+        rule: &Rule,
+        counter: &mut Counter,
+        outcomes: &mut OutcomeDistribution,
+        parallel: bool,
+    ) -> SolutionCheminDeFer {
+        let payouts = &rule.payouts;
+        let mut ex_player_win = |p_player_draw: f64, p_banker_draw: &[f64]| {
+            let strategy =
+                DrawingStrategy::chemin_de_fer(&rule.tableau, p_player_draw, p_banker_draw);
+            calculate_outcomes(counter, &strategy, outcomes, parallel);
+            outcomes.get_ex(|outcome| match outcome.winner() {
+                Winner::Player => payouts.player_win,
+                Winner::Banker => -1.0,
+                Winner::Tie => 0.0,
+            })
+        };
+
+        // Banker's options can never be reached in the same round, so with x
+        // being the probability that player draws on 5 and y[i] the probability
+        // that banker draws on option i, the expectation is
+        //     a + b * x + sum(y[i] * (c[i] + d[i] * x)).
+        let n = CHEMIN_DE_FER_BANKER_OPTIONS.len();
+        let stand = vec![0.0; n];
+        let a = ex_player_win(0.0, &stand);
+        let b = ex_player_win(1.0, &stand) - a;
+        let mut c = vec![0.0; n];
+        let mut d = vec![0.0; n];
+        for i in 0..n {
+            let mut y = stand.clone();
+            y[i] = 1.0;
+            c[i] = ex_player_win(0.0, &y) - a;
+            d[i] = ex_player_win(1.0, &y) - a - b - c[i];
+        }
+
+        // Player maximizes the expectation knowing that banker will respond
+        // with her best pure choices. The result is a concave piecewise linear
+        // function of x, so the maximum is at either end or a breakpoint.
+        let worst_case = |x: f64| {
+            a + b * x
+                + c.iter()
+                    .zip(&d)
+                    .map(|(c, d)| (c + d * x).min(0.0))
+                    .sum::<f64>()
+        };
+        let mut candidates = vec![(0.0, None), (1.0, None)];
+        for i in 0..n {
+            if d[i] != 0.0 {
+                let x = -c[i] / d[i];
+                if x > 0.0 && x < 1.0 {
+                    candidates.push((x, Some(i)));
+                }
+            }
+        }
+        let (x, breakpoint) = candidates
+            .into_iter()
+            .max_by(|(x1, _), (x2, _)| worst_case(*x1).total_cmp(&worst_case(*x2)))
+            .unwrap();
+
+        // Banker makes her best choices against x. When x is a breakpoint, she
+        // is indifferent on that option, and mixes it so that player is also
+        // indifferent between drawing and standing on 5.
+        let mut y: Vec<f64> = (0..n)
+            .map(|i| if c[i] + d[i] * x < 0.0 { 1.0 } else { 0.0 })
+            .collect();
+        if let Some(k) = breakpoint {
+            y[k] = 0.0;
+            let rest: f64 = b + y.iter().zip(&d).map(|(y, d)| y * d).sum::<f64>();
+            y[k] = (-rest / d[k]).clamp(0.0, 1.0);
+        }
+
+        SolutionCheminDeFer {
+            p_player_draw: x,
+            p_banker_draw: y,
+        }
+    }
+
+    fn deal_final_banker_card_loop(
+        bcr_counter: &[u32; 10],
+        player_sum: usize,
+        banker_sum: usize,
+        player_cards: u8,
+        p: f64,
+        total_count: u32,
+        outcomes: &mut OutcomeDistribution,
+    ) {
+        for (card, &count) in bcr_counter.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let p = p * count as f64 / total_count as f64;
+            let banker_sum = (banker_sum + card) % 10;
+            add_p_of_outcome(player_sum, banker_sum, player_cards, 3, outcomes, p);
+        }
+    }
+
+    fn add_p_of_outcome(
+        player_sum: usize,
+        banker_sum: usize,
+        player_cards: u8,
+        banker_cards: u8,
+        outcomes: &mut OutcomeDistribution,
+        p: f64,
+    ) {
+        let outcome = RoundOutcome {
+            player_total: player_sum as u8,
+            banker_total: banker_sum as u8,
+            player_cards,
+            banker_cards,
+        };
+        outcomes.add(outcome, p);
     }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
-    use crate::Payouts;
+    use crate::side_bet::{SideBet, SideBetCondition, SideBetOutcome, Winner};
+    use crate::{BankerWinOnTotal, Payouts};
 
-    fn get_typical_rule() -> Rule {
+    pub(crate) fn get_typical_rule() -> Rule {
         Rule {
             number_of_decks: 8,
             cut_card_proportion: 0.9,
             discard_at_start: true,
+            ez_baccarat: false,
+            chemin_de_fer: false,
+            tableau: Default::default(),
 
             payouts: Payouts {
                 player_win: 1.0,
                 banker_win: 0.95,
                 tie: 8.0,
 
+                banker_win_on_total: vec![],
+                tie_on_total: None,
+
                 unsuit_pair: 11.0,
                 either_pair: 5.0,
                 perfect_pair: [25.0, 200.0],
@@ -503,11 +1800,19 @@ mod tests {
                 bonus_unnatural: [1.0, 2.0, 4.0, 6.0, 10.0, 30.0],
                 bonus_natural_win: 1.0,
                 bonus_natural_tie: 0.0,
+
+                dragon_7: 40.0,
+                panda_8: 25.0,
+
+                big_small: None,
+                super_6: None,
             },
+
+            side_bets: vec![],
         }
     }
 
-    fn assert_float_equal(mut x: f64, mut y: f64) {
+    pub(crate) fn assert_float_equal(mut x: f64, mut y: f64) {
         const EPS: f64 = 0.0001;
         if x > y {
             (x, y) = (y, x);
@@ -542,4 +1847,674 @@ mod tests {
         assert_float_equal(1.0 + solution.sol_bonus.ex_player_bonus, RTP_PLAYER_BONUS);
         assert_float_equal(1.0 + solution.sol_bonus.ex_banker_bonus, RTP_BANKER_BONUS);
     }
+
+    #[test]
+    fn test_side_bets_declared_in_config() {
+        let mut rule = get_typical_rule();
+        rule.side_bets = vec![
+            // The same as the built-in tie bet.
+            SideBet {
+                name: String::from("Tie"),
+                outcomes: vec![SideBetOutcome {
+                    condition: SideBetCondition {
+                        winner: Some(Winner::Tie),
+                        ..Default::default()
+                    },
+                    payout: 8.0,
+                }],
+            },
+            // The same as the built-in player bonus bet.
+            SideBet {
+                name: String::from("Player Bonus"),
+                outcomes: [
+                    (Winner::Player, true, None, 1.0),
+                    (Winner::Tie, true, None, 0.0),
+                    (Winner::Player, false, Some(4), 1.0),
+                    (Winner::Player, false, Some(5), 2.0),
+                    (Winner::Player, false, Some(6), 4.0),
+                    (Winner::Player, false, Some(7), 6.0),
+                    (Winner::Player, false, Some(8), 10.0),
+                    (Winner::Player, false, Some(9), 30.0),
+                ]
+                .iter()
+                .map(|&(winner, player_natural, margin, payout)| SideBetOutcome {
+                    condition: SideBetCondition {
+                        winner: Some(winner),
+                        player_natural: Some(player_natural),
+                        margin,
+                        ..Default::default()
+                    },
+                    payout,
+                })
+                .collect(),
+            },
+        ];
+        let mut counter = Counter::new(8);
+        let mut solution: Solution = Default::default();
+
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        assert_eq!(solution.sol_side_bets.len(), 2);
+        assert_float_equal(solution.sol_side_bets[0].ex, solution.sol_main.ex_tie);
+        assert_float_equal(
+            solution.sol_side_bets[1].ex,
+            solution.sol_bonus.ex_player_bonus,
+        );
+    }
+
+    #[test]
+    fn test_ez_baccarat() {
+        // Ground truth based on 8 decks.
+        const EX_BANKER_WIN: f64 = -0.010183;
+        const EX_DRAGON_7: f64 = -0.076113;
+        const EX_PANDA_8: f64 = -0.101876;
+
+        let mut rule = get_typical_rule();
+        rule.ez_baccarat = true;
+        rule.payouts.banker_win = 1.0;
+        let mut counter = Counter::new(8);
+        let mut solution: Solution = Default::default();
+
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        let sol_ez = solution.sol_ez.as_ref().unwrap();
+        assert_float_equal(solution.sol_main.ex_banker_win, EX_BANKER_WIN);
+        assert_float_equal(sol_ez.ex_dragon_7, EX_DRAGON_7);
+        assert_float_equal(sol_ez.ex_panda_8, EX_PANDA_8);
+    }
+
+    #[test]
+    fn test_super_6() {
+        // Ground truth based on 8 decks.
+        const EX_BANKER_WIN: f64 = -0.014581;
+        const EX_SUPER_6: f64 = -0.299772;
+
+        let mut rule = get_typical_rule();
+        rule.payouts.banker_win = 1.0;
+        rule.payouts.banker_win_on_total = vec![BankerWinOnTotal {
+            total: 6,
+            cards: None,
+            payout: 0.5,
+        }];
+        rule.payouts.super_6 = Some([12.0, 12.0]);
+        let mut counter = Counter::new(8);
+        let mut solution: Solution = Default::default();
+
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        let p_banker_win: f64 = solution
+            .sol_main
+            .p_banker_win_by_total
+            .iter()
+            .flatten()
+            .sum();
+        assert_float_equal(p_banker_win, solution.sol_main.p_banker_win);
+        assert_float_equal(solution.sol_main.ex_banker_win, EX_BANKER_WIN);
+        assert_float_equal(solution.sol_super_6.unwrap().ex_super_6, EX_SUPER_6);
+    }
+
+    #[test]
+    fn test_outcome_distribution() {
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+        let (solution, outcomes) = solver.solve_with_outcomes(&Counter::new(8));
+
+        assert_float_equal(outcomes.get_p_where(|_| true), 1.0);
+        assert_float_equal(
+            outcomes.get_p_where(|o| o.winner() == Winner::Tie),
+            solution.sol_main.p_tie,
+        );
+        assert_float_equal(
+            outcomes.get_ex(|o| match o.winner() {
+                Winner::Player => 1.0,
+                Winner::Tie => 0.0,
+                Winner::Banker => -1.0,
+            }),
+            solution.sol_main.ex_player_win,
+        );
+        assert_eq!(
+            outcomes.get_p(&RoundOutcome {
+                player_total: 9,
+                banker_total: 9,
+                player_cards: 3,
+                banker_cards: 2,
+            }),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_tie_on_total() {
+        const PAYOUTS: [f64; 10] = [
+            150.0, 215.0, 225.0, 200.0, 120.0, 110.0, 45.0, 45.0, 80.0, 80.0,
+        ];
+
+        let mut rule = get_typical_rule();
+        rule.payouts.tie_on_total = Some(PAYOUTS);
+        let mut solver = Solver::new(&rule);
+        let (solution, outcomes) = solver.solve_with_outcomes(&Counter::new(8));
+
+        let s = &solution.sol_main;
+        assert_float_equal(s.p_tie_on_total.iter().sum(), s.p_tie);
+        for total in 0..10 {
+            let ex = outcomes.get_ex(|o| {
+                if o.winner() == Winner::Tie && o.player_total == total {
+                    PAYOUTS[total as usize]
+                } else {
+                    -1.0
+                }
+            });
+            assert_float_equal(s.ex_tie_on_total.unwrap()[total as usize], ex);
+        }
+    }
+
+    #[test]
+    fn test_rounds_remaining() {
+        assert_float_equal(get_expected_rounds_remaining(&[1.0, 0.0, 0.0], 0), 0.0);
+        assert_float_equal(get_expected_rounds_remaining(&[1.0, 0.0, 0.0], 8), 2.0);
+        assert_float_equal(get_expected_rounds_remaining(&[1.0, 0.0, 0.0], 9), 3.0);
+        assert_float_equal(get_expected_rounds_remaining(&[0.0, 0.0, 0.0], 9), 0.0);
+        // With 5 cards left, a 4-card round leaves another round.
+        assert_float_equal(get_expected_rounds_remaining(&[0.25, 0.0, 0.75], 5), 1.25);
+
+        // About 4.94 cards per round from a full shoe.
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+        let solution = solver.solve(&Counter::new(8));
+        let rounds = solution.get_expected_rounds_remaining(353);
+        assert!((71.0..73.0).contains(&rounds), "{}", rounds);
+    }
+
+    #[test]
+    fn test_big_small() {
+        // Ground truth based on 8 decks.
+        const EX_BIG: f64 = -0.043457;
+        const EX_SMALL: f64 = -0.052830;
+
+        let mut rule = get_typical_rule();
+        rule.payouts.big_small = Some([0.54, 1.5]);
+        let mut counter = Counter::new(8);
+        let mut solution: Solution = Default::default();
+
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        let s = solution.sol_big_small.unwrap();
+        assert_float_equal(s.p_big + s.p_small, 1.0);
+        assert_float_equal(solution.p_cards[0], s.p_small);
+        assert_float_equal(solution.p_cards[1] + solution.p_cards[2], s.p_big);
+        assert_float_equal(s.ex_big, EX_BIG);
+        assert_float_equal(s.ex_small, EX_SMALL);
+    }
+
+    #[test]
+    fn test_bets_not_offered() {
+        use crate::card::Suit;
+        use crate::game::{Hand, HandsResult, RoundResult};
+
+        // Player 9 beats banker 3, and none of the side bets is offered.
+        let rule = get_typical_rule();
+        let hand = |a, b| Hand {
+            initial: [Card::new(Suit::Spade, a), Card::new(Suit::Heart, b)],
+            third: None,
+        };
+        let (player, banker) = (hand(5, 4), hand(10, 3));
+        let not_offered = [
+            HandsBet::TieOn(3),
+            HandsBet::TieOn(12),
+            HandsBet::Dragon7,
+            HandsBet::Super6,
+            HandsBet::Big,
+            HandsBet::Small,
+            HandsBet::SideBet(0),
+        ];
+        let mut bets: HashMap<_, _> = not_offered.iter().map(|bet| (*bet, 100)).collect();
+        bets.insert(HandsBet::PlayerWin, 100);
+        let mut result = RoundResult::new(&rule);
+        result.calculate_with_hands_and_bet(&player, &banker, &bets);
+        assert_eq!(result.total_profit, 100);
+        let outcome = RoundOutcome::from_hands(&player, &banker);
+        for bet in &not_offered {
+            assert_eq!(result.details[bet].0, HandsResult::NotOffered);
+            assert_eq!(result.details[bet].1, 0);
+            assert_eq!(
+                crate::bet_sizing::get_profit_of_outcome(&rule, bet, &outcome),
+                Some(0.0)
+            );
+        }
+    }
+
+    #[test]
+    fn test_variance() {
+        // Ground truth based on 8 decks.
+        const VAR_PLAYER_WIN: f64 = 0.904691;
+        const VAR_BANKER_WIN: f64 = 0.860019;
+        const SKEW_BANKER_WIN: f64 = -0.032300;
+        const VAR_TIE: f64 = 6.974206;
+        const SKEW_TIE: f64 = 2.759389;
+
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+        let (solution, outcomes) = solver.solve_with_outcomes(&Counter::new(8));
+        let s = &solution.sol_main;
+        assert_float_equal(s.var_player_win, VAR_PLAYER_WIN);
+        assert_float_equal(s.var_banker_win, VAR_BANKER_WIN);
+        assert_float_equal(s.skew_banker_win, SKEW_BANKER_WIN);
+        assert_float_equal(s.var_tie, VAR_TIE);
+        assert_float_equal(s.skew_tie, SKEW_TIE);
+
+        // The variance of a bet is E[X^2] - E[X]^2, where X is the profit.
+        let banker_profit = |outcome: &RoundOutcome| match outcome.winner() {
+            Winner::Player => -1.0,
+            Winner::Banker => {
+                rule.get_banker_win_payout(outcome.banker_total, outcome.banker_cards)
+            }
+            Winner::Tie => 0.0,
+        };
+        let ex = outcomes.get_ex(banker_profit);
+        let var = outcomes.get_ex(|outcome| banker_profit(outcome).powi(2)) - ex * ex;
+        assert_float_equal(s.var_banker_win, var);
+        let skew = outcomes.get_ex(|outcome| (banker_profit(outcome) - ex).powi(3)) / var.powf(1.5);
+        assert_float_equal(s.skew_banker_win, skew);
+    }
+
+    #[test]
+    fn test_chemin_de_fer() {
+        let mut rule = get_typical_rule();
+        rule.chemin_de_fer = true;
+        let mut counter = Counter::new(8);
+        let mut solution: Solution = Default::default();
+
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        let s = solution.sol_chemin_de_fer.clone().unwrap();
+        let ex = solution.sol_main.ex_player_win;
+        assert_eq!(s.p_banker_draw.len(), CHEMIN_DE_FER_BANKER_OPTIONS.len());
+
+        // Neither side can do better by changing her own strategy alone.
+        let mut outcomes = OutcomeDistribution::default();
+        let mut ex_player_win = |p_player_draw: f64, p_banker_draw: &[f64]| {
+            let strategy =
+                DrawingStrategy::chemin_de_fer(&rule.tableau, p_player_draw, p_banker_draw);
+            functional::calculate_outcomes_with_strategy(&mut counter, &strategy, &mut outcomes);
+            outcomes.get_ex(|outcome| match outcome.winner() {
+                Winner::Player => 1.0,
+                Winner::Banker => -1.0,
+                Winner::Tie => 0.0,
+            })
+        };
+        assert_float_equal(ex_player_win(s.p_player_draw, &s.p_banker_draw), ex);
+        for p_player_draw in [0.0, 1.0] {
+            assert!(ex_player_win(p_player_draw, &s.p_banker_draw) <= ex + 1e-12);
+        }
+        for i in 0..CHEMIN_DE_FER_BANKER_OPTIONS.len() {
+            for p in [0.0, 1.0] {
+                let mut p_banker_draw = s.p_banker_draw.clone();
+                p_banker_draw[i] = p;
+                assert!(ex_player_win(s.p_player_draw, &p_banker_draw) >= ex - 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_tableau() {
+        // Ground truth based on 8 decks, where player stands on 5.
+        const EX_PLAYER_WIN: f64 = -0.015214;
+        const EX_BANKER_WIN: f64 = -0.007869;
+
+        let mut rule = get_typical_rule();
+        rule.tableau.player = "DDDDDSSSSS".to_string().try_into().unwrap();
+        let mut counter = Counter::new(8);
+        let mut solution: Solution = Default::default();
+
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        let s = &solution.sol_main;
+        assert_float_equal(s.p_player_win + s.p_banker_win + s.p_tie, 1.0);
+        assert_float_equal(s.ex_player_win, EX_PLAYER_WIN);
+        assert_float_equal(s.ex_banker_win, EX_BANKER_WIN);
+        assert_eq!(String::from(rule.tableau.player), "DDDDDSSSSS".to_string());
+    }
+
+    #[test]
+    fn test_errors() {
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+        assert_eq!(
+            Counter::try_with_card_count(&[1; 51]).err(),
+            Some(Error::InvalidCardCountLength(51))
+        );
+
+        let mut card_count = [0; 52];
+        card_count[0] = 4;
+        let mut counter = Counter::try_with_card_count(&card_count).unwrap();
+        assert!(solver.try_solve(&counter).is_ok());
+
+        let card = Card::from_index(0);
+        assert_eq!(counter.try_remove_card(card), Ok(()));
+        assert_eq!(
+            solver.try_solve(&counter).err(),
+            Some(Error::NotEnoughCards(3))
+        );
+
+        // The products of such counts would overflow.
+        assert_eq!(
+            Counter::try_with_card_count(&[100_000; 52]).err(),
+            Some(Error::CardCountTooLarge(Card::from_index(0), 100_000))
+        );
+        let mut card_count = [MAX_DECKS; 52];
+        card_count[51] += 1;
+        assert_eq!(
+            Counter::try_with_card_count(&card_count).err(),
+            Some(Error::CardCountTooLarge(
+                Card::from_index(51),
+                MAX_DECKS + 1
+            ))
+        );
+        let oversized = Counter::with_card_count(&card_count);
+        assert!(solver.try_solve(&oversized).is_err());
+        assert!(solver.try_solve(&Counter::new(MAX_DECKS)).is_ok());
+
+        let card = Card::from_index(1);
+        assert_eq!(
+            counter.try_remove_card(card),
+            Err(Error::CardNotFound(card))
+        );
+        assert_eq!(counter.get_total(), 3);
+    }
+
+    #[test]
+    fn test_small_shoe() {
+        let rule = get_typical_rule();
+        let mut solution: Solution = Default::default();
+
+        // A 7 and four Kings. Whoever gets the 7 wins, unless it's the last
+        // card, which player draws and then banker cannot draw.
+        let mut card_count = [0; 52];
+        card_count[6] = 1;
+        card_count[12] = 4;
+        let mut counter = Counter::with_card_count(&card_count);
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        let s = &solution.sol_main;
+        assert_float_equal(solution.p_incomplete, 0.2);
+        assert_float_equal(s.p_player_win, 0.4);
+        assert_float_equal(s.p_banker_win, 0.4);
+        assert_eq!(s.p_tie, 0.0);
+        assert_float_equal(s.ex_tie, -0.8);
+        assert_float_equal(solution.sol_pair.p_unsuit_pair, 0.6);
+
+        // Not even a round can be started.
+        let mut card_count = [0; 52];
+        card_count[0] = 3;
+        let mut counter = Counter::with_card_count(&card_count);
+        functional::calculate(&rule, &mut counter, &mut solution);
+
+        assert_eq!(solution.p_incomplete, 1.0);
+        assert_eq!(solution.sol_main.ex_banker_win, 0.0);
+        assert_eq!(solution.sol_main.ex_tie, 0.0);
+        assert_eq!(solution.sol_pair, Default::default());
+    }
+
+    #[test]
+    fn test_parallel_solver() {
+        let rule = get_typical_rule();
+        let mut solver = Solver::new(&rule);
+        let mut parallel_solver = ParallelSolver::new(&rule);
+        let mut cache = SolutionCache::new(2);
+
+        let mut counter = Counter::new(8);
+        let mut counters = vec![];
+        for i in 0..3 {
+            counter.remove_card(Card::from_index(i));
+            counters.push(counter.clone());
+        }
+
+        for counter in &counters {
+            let expected = solver.solve(counter).clone();
+            let solution = parallel_solver.solve(counter);
+            cache.insert(counter.clone(), solution.clone());
+            assert_float_equal(
+                solution.sol_main.ex_player_win,
+                expected.sol_main.ex_player_win,
+            );
+            assert_float_equal(solution.sol_main.ex_tie, expected.sol_main.ex_tie);
+            assert_float_equal(
+                solution.sol_pair.ex_either_pair,
+                expected.sol_pair.ex_either_pair,
+            );
+        }
+        assert!(!cache.contains(&counters[0]));
+        assert!(cache.contains(&counters[1]));
+        assert!(cache.contains(&counters[2]));
+
+        // The cached solver gives the same solutions, whether they're cached
+        // or not.
+        let mut cached_solver = CachedSolver::new(Solver::new(&rule), 2);
+        for counter in counters.iter().chain(&counters) {
+            let expected = solver.solve(counter).clone();
+            assert_eq!(cached_solver.solve(counter), &expected);
+        }
+    }
+
+    #[test]
+    fn test_incremental_solver() {
+        let mut rule = get_typical_rule();
+        rule.payouts.big_small = Some([0.54, 1.5]);
+        let mut solver = Solver::new(&rule);
+        let mut counter = Counter::new(8);
+        let mut incremental_solver = incremental::IncrementalSolver::new(&rule, &counter);
+
+        let mut check = |counter: &Counter, solution: &Solution| {
+            let expected = solver.solve(counter);
+            assert_float_equal(solution.p_incomplete, expected.p_incomplete);
+            assert_float_equal(
+                solution.sol_main.ex_player_win,
+                expected.sol_main.ex_player_win,
+            );
+            assert_float_equal(
+                solution.sol_main.ex_banker_win,
+                expected.sol_main.ex_banker_win,
+            );
+            assert_float_equal(solution.sol_main.ex_tie, expected.sol_main.ex_tie);
+            assert_float_equal(
+                solution.sol_bonus.ex_player_bonus,
+                expected.sol_bonus.ex_player_bonus,
+            );
+            let (s, e) = (
+                solution.sol_big_small.as_ref().unwrap(),
+                expected.sol_big_small.as_ref().unwrap(),
+            );
+            assert_float_equal(s.ex_big, e.ex_big);
+        };
+
+        for i in [0, 9, 13, 22, 51, 9] {
+            let card = Card::from_index(i);
+            counter.remove_card(card);
+            incremental_solver.remove_card(card).unwrap();
+            check(&counter, incremental_solver.get_solution());
+        }
+        let card = Card::from_index(9);
+        counter.add_card(card);
+        incremental_solver.add_card(card);
+        check(&counter, incremental_solver.get_solution());
+
+        // Jump to a totally different counter, including a nearly empty shoe.
+        let mut card_count = [0; 52];
+        card_count[6] = 1;
+        card_count[12] = 4;
+        let counter = Counter::with_card_count(&card_count);
+        let solution = incremental_solver.solve(&counter).clone();
+        check(&counter, &solution);
+        assert_float_equal(solution.p_incomplete, 0.2);
+    }
+
+    #[cfg(feature = "exact")]
+    #[test]
+    fn test_exact() {
+        use num_traits::{One, ToPrimitive};
+
+        let rule = get_typical_rule();
+        let mut exact_solver = exact::ExactSolver::new(&rule);
+        let counter = Counter::new(8);
+        let exact = exact_solver.solve(&counter).unwrap();
+        assert_eq!(
+            &exact.p_player_win + &exact.p_banker_win + &exact.p_tie,
+            num_rational::BigRational::one()
+        );
+        assert_eq!(exact.p_unsuit_pair.to_string(), "31/415");
+
+        let mut solver = Solver::new(&rule);
+        let solution = solver.solve(&counter);
+        assert_float_equal(
+            exact.ex_banker_win.to_f64().unwrap(),
+            solution.sol_main.ex_banker_win,
+        );
+        assert_float_equal(
+            exact.ex_player_win.to_f64().unwrap(),
+            solution.sol_main.ex_player_win,
+        );
+
+        let mut rule = rule.clone();
+        rule.chemin_de_fer = true;
+        assert_eq!(
+            exact::calculate(&rule, &counter),
+            Err(Error::Unsupported("exact solution of Chemin de fer"))
+        );
+    }
+
+    #[cfg(feature = "exact")]
+    mod exact_oracle {
+        use super::*;
+        use num_traits::ToPrimitive;
+        use proptest::prelude::*;
+
+        #[track_caller]
+        fn assert_close(exact: &num_rational::BigRational, x: f64) {
+            let y = exact.to_f64().unwrap();
+            assert!((x - y).abs() < 1e-12, "{} != {}", x, y);
+        }
+
+        #[track_caller]
+        fn assert_all_close(exact: &[num_rational::BigRational], xs: &[f64]) {
+            assert_eq!(exact.len(), xs.len());
+            for (exact, x) in exact.iter().zip(xs) {
+                assert_close(exact, *x);
+            }
+        }
+
+        /// Offers every bet that the solution has, with payouts that make
+        /// mistakes show.
+        fn get_rule_with_all_bets() -> Rule {
+            let mut rule = get_typical_rule();
+            rule.ez_baccarat = true;
+            rule.payouts.banker_win_on_total = vec![BankerWinOnTotal {
+                total: 6,
+                cards: Some(2),
+                payout: 0.5,
+            }];
+            rule.payouts.tie_on_total = Some([
+                150.0, 215.0, 225.0, 200.0, 120.0, 110.0, 45.0, 45.0, 80.0, 80.0,
+            ]);
+            rule.payouts.super_6 = Some([12.0, 20.0]);
+            rule.payouts.big_small = Some([0.54, 1.5]);
+            rule.side_bets = vec![SideBet {
+                name: String::from("Natural Margin"),
+                outcomes: vec![
+                    SideBetOutcome {
+                        condition: SideBetCondition {
+                            player_natural: Some(true),
+                            banker_natural: Some(true),
+                            ..Default::default()
+                        },
+                        payout: 4.0,
+                    },
+                    SideBetOutcome {
+                        condition: SideBetCondition {
+                            margin: Some(9),
+                            ..Default::default()
+                        },
+                        payout: 25.0,
+                    },
+                ],
+            }];
+            rule
+        }
+
+        proptest! {
+            #![proptest_config(ProptestConfig::with_cases(32))]
+
+            #[test]
+            fn floating_point_solver_matches_exact(card_count in prop::collection::vec(0u32..4, 52)) {
+                let counter = Counter::try_with_card_count(&card_count).unwrap();
+                prop_assume!(counter.get_total() >= MIN_CARDS_PER_ROUND);
+
+                let rule = get_rule_with_all_bets();
+                let exact = exact::calculate(&rule, &counter).unwrap();
+                let mut solver = Solver::new(&rule);
+                let solution = solver.solve(&counter);
+
+                assert_close(&exact.p_incomplete, solution.p_incomplete);
+                let s = &solution.sol_main;
+                assert_close(&exact.p_player_win, s.p_player_win);
+                assert_close(&exact.p_banker_win, s.p_banker_win);
+                assert_close(&exact.p_tie, s.p_tie);
+                assert_close(&exact.ex_player_win, s.ex_player_win);
+                assert_close(&exact.ex_banker_win, s.ex_banker_win);
+                assert_close(&exact.ex_tie, s.ex_tie);
+                for (e, p) in exact.p_banker_win_by_total.iter().zip(&s.p_banker_win_by_total) {
+                    assert_all_close(e, p);
+                }
+                assert_all_close(&exact.p_tie_on_total, &s.p_tie_on_total);
+                assert_all_close(
+                    exact.ex_tie_on_total.as_ref().unwrap(),
+                    &s.ex_tie_on_total.unwrap(),
+                );
+
+                let s = &solution.sol_pair;
+                assert_close(&exact.p_unsuit_pair, s.p_unsuit_pair);
+                assert_close(&exact.ex_unsuit_pair, s.ex_unsuit_pair);
+                assert_close(&exact.p_either_pair, s.p_either_pair);
+                assert_close(&exact.ex_either_pair, s.ex_either_pair);
+                assert_all_close(&exact.p_suit_pair, &s.p_suit_pair);
+                assert_close(&exact.ex_suit_pair, s.ex_suit_pair);
+
+                let s = &solution.sol_bonus;
+                assert_all_close(&exact.p_player_bonus_unnatural, &s.p_player_bonus_unnatural);
+                assert_all_close(&exact.p_banker_bonus_unnatural, &s.p_banker_bonus_unnatural);
+                assert_close(&exact.p_player_bonus_natural_win, s.p_player_bonus_natural_win);
+                assert_close(&exact.p_banker_bonus_natural_win, s.p_banker_bonus_natural_win);
+                assert_close(&exact.p_bonus_natural_tie, s.p_bonus_natural_tie);
+                assert_close(&exact.ex_player_bonus, s.ex_player_bonus);
+                assert_close(&exact.ex_banker_bonus, s.ex_banker_bonus);
+
+                let (e, s) = (exact.sol_ez.as_ref().unwrap(), solution.sol_ez.as_ref().unwrap());
+                assert_close(&e.p_dragon_7, s.p_dragon_7);
+                assert_close(&e.ex_dragon_7, s.ex_dragon_7);
+                assert_close(&e.p_panda_8, s.p_panda_8);
+                assert_close(&e.ex_panda_8, s.ex_panda_8);
+
+                let (e, s) = (
+                    exact.sol_super_6.as_ref().unwrap(),
+                    solution.sol_super_6.as_ref().unwrap(),
+                );
+                assert_all_close(&e.p_super_6, &s.p_super_6);
+                assert_close(&e.ex_super_6, s.ex_super_6);
+
+                let (e, s) = (
+                    exact.sol_big_small.as_ref().unwrap(),
+                    solution.sol_big_small.as_ref().unwrap(),
+                );
+                assert_close(&e.p_big, s.p_big);
+                assert_close(&e.ex_big, s.ex_big);
+                assert_close(&e.p_small, s.p_small);
+                assert_close(&e.ex_small, s.ex_small);
+
+                assert_eq!(exact.sol_side_bets.len(), solution.sol_side_bets.len());
+                for (e, s) in exact.sol_side_bets.iter().zip(&solution.sol_side_bets) {
+                    assert_all_close(&e.p, &s.p);
+                    assert_close(&e.ex, s.ex);
+                }
+            }
+        }
+    }
 }
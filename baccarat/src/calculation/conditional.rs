@@ -0,0 +1,457 @@
+//! Solving a round some of whose cards are already known, e.g. when side
+//! bets are open after the first cards are shown, or the cards are squeezed
+//! one at a time.
+
+use super::{functional, Counter, OutcomeDistribution, Solution, SolutionPair};
+use crate::{card::Card, game::RoundOutcome, tableau::Tableau, Error, Rule};
+
+/// The known cards of a hand. None stands for a card not dealt or not shown
+/// yet.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct KnownCards {
+    pub initial: [Option<Card>; 2],
+    pub third: Option<Card>,
+}
+
+impl KnownCards {
+    pub fn is_empty(&self) -> bool {
+        self.initial == [None, None] && self.third.is_none()
+    }
+}
+
+/// Calculates the solution of the round given the known cards of the hands.
+/// The counter has the cards left in the shoe, i.e. the known cards must
+/// already be removed from it. The unknown cards are equally likely to be any
+/// of the cards in the counter, as long as they're consistent with the known
+/// cards and the tableau.
+///
+/// A third card can only be known if the initial cards of its hand are,
+/// since they're shown before drawing. Chemin de fer isn't supported.
+pub fn calculate(
+    rule: &Rule,
+    counter: &Counter,
+    player: &KnownCards,
+    banker: &KnownCards,
+) -> Result<Solution, Error> {
+    if rule.chemin_de_fer {
+        return Err(Error::Unsupported("conditional solution of Chemin de fer"));
+    }
+    if [player, banker]
+        .iter()
+        .any(|hand| hand.third.is_some() && hand.initial.contains(&None))
+    {
+        return Err(Error::Unsupported(
+            "third card of a hand whose initial cards are unknown",
+        ));
+    }
+    let mut solution = Solution::default();
+    if player.is_empty() && banker.is_empty() {
+        functional::try_calculate(rule, &mut counter.clone(), &mut solution)?;
+        return Ok(solution);
+    }
+
+    // In the order of dealing.
+    let mut initial = [
+        player.initial[0],
+        banker.initial[0],
+        player.initial[1],
+        banker.initial[1],
+    ];
+    let unknown = initial.iter().filter(|card| card.is_none()).count() as u32;
+    if counter.total < unknown {
+        return Err(Error::NotEnoughCards(counter.total));
+    }
+
+    let mut enumeration = Enumeration {
+        tableau: &rule.tableau,
+        thirds: [player.third, banker.third],
+        pair_outcomes: Default::default(),
+        pairs: Default::default(),
+        p_consistent: 0.0,
+    };
+    enumeration.enumerate_initial(&mut counter.clone(), &mut initial, 0, 1.0);
+    let z = enumeration.p_consistent;
+    if z == 0.0 {
+        return Err(Error::InconsistentHands);
+    }
+
+    let mut pair_outcomes = enumeration.pair_outcomes;
+    let mut outcomes = OutcomeDistribution::default();
+    for sub in pair_outcomes.iter_mut().flatten() {
+        sub.p
+            .iter_mut()
+            .flatten()
+            .flatten()
+            .flatten()
+            .for_each(|p| *p /= z);
+        sub.p_incomplete /= z;
+        outcomes.merge(sub);
+    }
+    functional::calculate_based_on_outcomes(rule, &mut counter.clone(), &outcomes, &mut solution);
+    solution.pair_outcomes = pair_outcomes;
+
+    // The pair bets are settled by the initial cards, whatever the counter.
+    let pairs = enumeration.pairs;
+    solution.sol_pair = SolutionPair {
+        p_unsuit_pair: pairs.player / z,
+        p_banker_unsuit_pair: Some(pairs.banker / z),
+        p_either_pair: pairs.either / z,
+        p_suit_pair: pairs.suit.map(|p| p / z),
+        p_joint: pairs.joint.map(|row| row.map(|p| p / z)),
+        ..Default::default()
+    };
+    solution.calculate_ex_based_on_p(rule);
+    Ok(solution)
+}
+
+struct Enumeration<'a> {
+    tableau: &'a Tableau,
+    thirds: [Option<Card>; 2], // The known third cards of player and banker.
+    // The following are not normalized by p_consistent yet.
+    pair_outcomes: [[OutcomeDistribution; 3]; 3], // See Solution::pair_outcomes.
+    pairs: PairProbabilities,
+    p_consistent: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct PairProbabilities {
+    player: f64,
+    banker: f64,
+    either: f64,
+    suit: [f64; 2],       // Either and both hands are perfect pairs.
+    joint: [[f64; 3]; 3], // See SolutionPair::p_joint.
+}
+
+impl<'a> Enumeration<'a> {
+    /// Deals the unknown initial cards by card, since pair bets care about
+    /// ranks and suits.
+    fn enumerate_initial(
+        &mut self,
+        shoe: &mut Counter,
+        initial: &mut [Option<Card>; 4],
+        slot: usize,
+        p: f64,
+    ) {
+        if slot == 4 {
+            let initial = initial.map(Option::unwrap);
+            return self.add_initial(shoe, &initial, p);
+        }
+        if initial[slot].is_some() {
+            return self.enumerate_initial(shoe, initial, slot + 1, p);
+        }
+        let total = shoe.total as f64;
+        for i in 0..52 {
+            let count = shoe.card_count[i];
+            if count == 0 {
+                continue;
+            }
+            let card = Card::from_index(i);
+            shoe.remove_card(card);
+            initial[slot] = Some(card);
+            self.enumerate_initial(shoe, initial, slot + 1, p * count as f64 / total);
+            initial[slot] = None;
+            shoe.add_card(card);
+        }
+    }
+
+    fn add_initial(&mut self, shoe: &Counter, initial: &[Card; 4], p: f64) {
+        let (player, banker) = ([initial[0], initial[2]], [initial[1], initial[3]]);
+        let is_pair = |hand: [Card; 2]| hand[0].value == hand[1].value;
+        let is_perfect = |hand: [Card; 2]| hand[0] == hand[1];
+        let kind = |hand| is_pair(hand) as usize + is_perfect(hand) as usize;
+        let outcomes = &mut self.pair_outcomes[kind(player)][kind(banker)];
+        let p_consistent =
+            p * Self::add_thirds(self.tableau, &self.thirds, outcomes, shoe, initial, p);
+        if p_consistent == 0.0 {
+            return;
+        }
+        self.p_consistent += p_consistent;
+
+        let pairs = &mut self.pairs;
+        if is_pair(player) {
+            pairs.player += p_consistent;
+        }
+        if is_pair(banker) {
+            pairs.banker += p_consistent;
+        }
+        if is_pair(player) || is_pair(banker) {
+            pairs.either += p_consistent;
+        }
+        match is_perfect(player) as usize + is_perfect(banker) as usize {
+            1 => pairs.suit[0] += p_consistent,
+            2 => pairs.suit[1] += p_consistent,
+            _ => {}
+        }
+        pairs.joint[kind(player)][kind(banker)] += p_consistent;
+    }
+
+    /// Deals the third cards (by value, which is all that matters now) after
+    /// the initial cards, whose probability is p, and adds the outcomes.
+    /// Returns the probability that the third cards are consistent with the
+    /// known ones.
+    fn add_thirds(
+        tableau: &Tableau,
+        thirds: &[Option<Card>; 2],
+        outcomes: &mut OutcomeDistribution,
+        shoe: &Counter,
+        initial: &[Card; 4],
+        p: f64,
+    ) -> f64 {
+        let value = |card: Card| card.to_bcr_value_index() as u8;
+        let player_total = (value(initial[0]) + value(initial[2])) % 10;
+        let banker_total = (value(initial[1]) + value(initial[3])) % 10;
+        let is_natural = player_total >= 8 || banker_total >= 8;
+        let player_draws = !is_natural && tableau.should_player_draw(player_total);
+        if thirds[0].is_some() && !player_draws {
+            return 0.0;
+        }
+
+        let mut values = shoe.bcr_value_count;
+        let mut p_consistent = 0.0;
+        for (player_third, p_player) in get_third_options(player_draws, thirds[0], &values) {
+            let Some(player_third) = player_third else {
+                // The shoe runs out.
+                outcomes.p_incomplete += p * p_player;
+                p_consistent += p_player;
+                continue;
+            };
+            let player_third = player_third.map(|v| v as u8);
+            let banker_draws =
+                !is_natural && tableau.should_banker_draw(banker_total, player_third);
+            if thirds[1].is_some() && !banker_draws {
+                continue;
+            }
+            if let Some(v) = player_third {
+                if thirds[0].is_none() {
+                    values[v as usize] -= 1;
+                }
+            }
+
+            let banker_options = get_third_options(banker_draws, thirds[1], &values);
+            for (banker_third, p_banker) in banker_options {
+                p_consistent += p_player * p_banker;
+                let Some(banker_third) = banker_third else {
+                    outcomes.p_incomplete += p * p_player * p_banker;
+                    continue;
+                };
+                let banker_third = banker_third.map(|v| v as u8);
+                let outcome = RoundOutcome {
+                    player_total: (player_total + player_third.unwrap_or(0)) % 10,
+                    banker_total: (banker_total + banker_third.unwrap_or(0)) % 10,
+                    player_cards: 2 + player_third.is_some() as u8,
+                    banker_cards: 2 + banker_third.is_some() as u8,
+                };
+                outcomes.add(outcome, p * p_player * p_banker);
+            }
+
+            if let Some(v) = player_third {
+                if thirds[0].is_none() {
+                    values[v as usize] += 1;
+                }
+            }
+        }
+        p_consistent
+    }
+}
+
+/// Returns the possible third cards (by value) of a hand and their
+/// probabilities. Some(None) means the hand stands, and None means the shoe
+/// runs out of cards.
+fn get_third_options(
+    draws: bool,
+    known: Option<Card>,
+    values: &[u32; 10],
+) -> Vec<(Option<Option<usize>>, f64)> {
+    if !draws {
+        return vec![(Some(None), 1.0)];
+    }
+    if let Some(card) = known {
+        return vec![(Some(Some(card.to_bcr_value_index())), 1.0)];
+    }
+    let total: u32 = values.iter().sum();
+    if total == 0 {
+        return vec![(None, 1.0)];
+    }
+    (0..10)
+        .filter(|&v| values[v] > 0)
+        .map(|v| (Some(Some(v)), values[v] as f64 / total as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculation::tests::{assert_float_equal, get_typical_rule};
+    use crate::card::Suit;
+
+    #[test]
+    fn test_conditional() {
+        let rule = get_typical_rule();
+        let known = |initial: [usize; 2], third: Option<usize>| KnownCards {
+            initial: initial.map(|i| Some(Card::from_index(i))),
+            third: third.map(Card::from_index),
+        };
+
+        // Player has a natural 9, and banker has 4 + 3 = 7.
+        let mut counter = Counter::new(8);
+        for i in [3, 4, 16, 2] {
+            counter.remove_card(Card::from_index(i));
+        }
+        let player = known([3, 4], None);
+        let banker = known([16, 2], None);
+        let solution = calculate(&rule, &counter, &player, &banker).unwrap();
+        assert_float_equal(solution.sol_main.p_player_win, 1.0);
+        assert_float_equal(solution.p_cards[0], 1.0);
+        assert_float_equal(solution.sol_pair.p_unsuit_pair, 0.0);
+        assert_float_equal(solution.sol_pair.ex_suit_pair, -1.0);
+
+        // Player can't draw on a natural.
+        let player = known([3, 4], Some(10));
+        assert_eq!(
+            calculate(&rule, &counter, &player, &banker),
+            Err(Error::InconsistentHands)
+        );
+        let player = KnownCards {
+            third: Some(Card::from_index(10)),
+            ..Default::default()
+        };
+        assert!(calculate(&rule, &counter, &player, &KnownCards::default()).is_err());
+
+        // Averaging over the first card gives the unconditional solution.
+        let mut card_count = [0; 52];
+        for (i, count) in [(0, 3), (4, 2), (9, 3), (13, 2), (17, 1), (20, 2), (35, 2)] {
+            card_count[i] = count;
+        }
+        let counter = Counter::with_card_count(&card_count);
+        let mut expected = Solution::default();
+        functional::calculate(&rule, &mut counter.clone(), &mut expected);
+        let mut averaged = Solution::default();
+        for (i, &count) in card_count.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let card = Card::from_index(i);
+            let mut counter = counter.clone();
+            counter.remove_card(card);
+            let player = KnownCards {
+                initial: [Some(card), None],
+                third: None,
+            };
+            let s = calculate(&rule, &counter, &player, &KnownCards::default()).unwrap();
+            let w = count as f64 / 15.0;
+            averaged.sol_main.p_player_win += w * s.sol_main.p_player_win;
+            averaged.sol_main.p_tie += w * s.sol_main.p_tie;
+            averaged.sol_pair.p_unsuit_pair += w * s.sol_pair.p_unsuit_pair;
+            averaged.sol_pair.p_either_pair += w * s.sol_pair.p_either_pair;
+            averaged.sol_pair.p_suit_pair[1] += w * s.sol_pair.p_suit_pair[1];
+            averaged.p_incomplete += w * s.p_incomplete;
+        }
+        assert_float_equal(
+            averaged.sol_main.p_player_win,
+            expected.sol_main.p_player_win,
+        );
+        assert_float_equal(averaged.sol_main.p_tie, expected.sol_main.p_tie);
+        assert_float_equal(
+            averaged.sol_pair.p_unsuit_pair,
+            expected.sol_pair.p_unsuit_pair,
+        );
+        assert_float_equal(
+            averaged.sol_pair.p_either_pair,
+            expected.sol_pair.p_either_pair,
+        );
+        assert_float_equal(
+            averaged.sol_pair.p_suit_pair[1],
+            expected.sol_pair.p_suit_pair[1],
+        );
+        assert_float_equal(averaged.p_incomplete, expected.p_incomplete);
+
+        // Only player's cards are known, so Player Pair is decided but Banker
+        // Pair isn't.
+        let mut counter = Counter::new(1);
+        let (a, b) = (Card::new(Suit::Heart, 7), Card::new(Suit::Spade, 7));
+        counter.remove_card(a);
+        counter.remove_card(b);
+        let player = KnownCards {
+            initial: [Some(a), Some(b)],
+            third: None,
+        };
+        let solution = calculate(&rule, &counter, &player, &KnownCards::default()).unwrap();
+        assert_float_equal(solution.sol_pair.p_unsuit_pair, 1.0);
+        let p_banker = solution.sol_pair.p_banker_unsuit_pair.unwrap();
+        assert!(p_banker > 0.0 && p_banker < 0.1);
+        // Player Pair has already won, while Banker Pair is a long shot.
+        assert_eq!(solution.sol_pair.var_unsuit_pair, 0.0);
+        let payout = rule.payouts.unsuit_pair;
+        assert_float_equal(
+            solution.sol_pair.var_banker_unsuit_pair.unwrap(),
+            p_banker * (1.0 - p_banker) * (payout + 1.0).powi(2),
+        );
+        let skew = solution.sol_pair.skew_banker_unsuit_pair.unwrap();
+        assert_float_equal(
+            skew,
+            (1.0 - 2.0 * p_banker) / (p_banker * (1.0 - p_banker)).sqrt(),
+        );
+        assert_float_equal(solution.sol_pair.p_either_pair, 1.0);
+        assert_float_equal(solution.sol_pair.p_joint[1].iter().sum(), 1.0);
+        assert_float_equal(
+            solution.sol_pair.p_joint[1][1] + solution.sol_pair.p_joint[1][2],
+            p_banker,
+        );
+    }
+
+    #[test]
+    fn test_pair_outcomes() {
+        let rule = get_typical_rule();
+        let cards = [
+            (Suit::Diamond, 6),
+            (Suit::Diamond, 6),
+            (Suit::Club, 6),
+            (Suit::Heart, 6),
+            (Suit::Club, 13),
+            (Suit::Club, 13),
+            (Suit::Club, 12),
+            (Suit::Heart, 13),
+            (Suit::Spade, 3),
+            (Suit::Diamond, 3),
+            (Suit::Spade, 9),
+            (Suit::Heart, 4),
+        ];
+        // The solver splits the rounds by value, while the enumeration deals
+        // the initial cards one by one. Also with too few cards to complete
+        // some rounds.
+        for len in [cards.len(), 5] {
+            let mut counter = Counter::new(0);
+            for &(suit, value) in &cards[..len] {
+                counter.add_card(Card::new(suit, value));
+            }
+            let mut solution = Solution::default();
+            functional::calculate(&rule, &mut counter.clone(), &mut solution);
+
+            let mut enumeration = Enumeration {
+                tableau: &rule.tableau,
+                thirds: [None, None],
+                pair_outcomes: Default::default(),
+                pairs: Default::default(),
+                p_consistent: 0.0,
+            };
+            enumeration.enumerate_initial(&mut counter, &mut [None; 4], 0, 1.0);
+            assert_float_equal(enumeration.p_consistent, 1.0);
+            // Player Pair, perfect or not.
+            let p_player_pair: f64 = solution.pair_outcomes[1..]
+                .iter()
+                .flatten()
+                .map(|outcomes| {
+                    outcomes.p_incomplete + outcomes.iter().map(|(_, p)| p).sum::<f64>()
+                })
+                .sum();
+            assert_float_equal(p_player_pair, solution.sol_pair.p_unsuit_pair);
+            let expected = enumeration.pair_outcomes.iter().flatten();
+            for (actual, expected) in solution.pair_outcomes.iter().flatten().zip(expected) {
+                for ((_, p), (_, q)) in actual.iter().zip(expected.iter()) {
+                    assert!((p - q).abs() < 1e-12);
+                }
+                assert!((actual.p_incomplete - expected.p_incomplete).abs() < 1e-12);
+            }
+        }
+    }
+}
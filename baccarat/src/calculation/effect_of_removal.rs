@@ -0,0 +1,102 @@
+//! Effect of removal (EOR): how much the ex of each bet changes when a single
+//! card is removed from a full shoe. A linear counting system tags each card
+//! roughly in proportion to its EOR on the bet it's designed for.
+
+use super::{functional, Counter, Solution};
+use crate::{bet_sizing, card::Card, game::HandsBet, Error, Rule};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EffectOfRemoval {
+    /// All the bets offered under the rule. The other fields are indexed by
+    /// the bets in this order.
+    pub bets: Vec<HandsBet>,
+    /// The ex of each bet from the full shoe.
+    pub ex: Vec<f64>,
+    /// by_rank[r][i] is the change of the ex of bet i when a card of rank
+    /// r + 1 (i.e., A is 0 and K is 12) is removed.
+    pub by_rank: Vec<Vec<f64>>,
+    /// by_card[c][i] is the change of the ex of bet i when the card of index c
+    /// (see Card::from_index) is removed. It only differs from by_rank in
+    /// bets that care about suits, e.g. Perfect Pair.
+    pub by_card: Vec<Vec<f64>>,
+}
+
+impl EffectOfRemoval {
+    /// Returns the EOR of each rank on the bet, or None if it isn't offered.
+    pub fn get_by_rank(&self, bet: HandsBet) -> Option<[f64; 13]> {
+        let i = self.bets.iter().position(|b| *b == bet)?;
+        Some(std::array::from_fn(|r| self.by_rank[r][i]))
+    }
+
+    /// Returns the EOR of each card on the bet, or None if it isn't offered.
+    pub fn get_by_card(&self, bet: HandsBet) -> Option<[f64; 52]> {
+        let i = self.bets.iter().position(|b| *b == bet)?;
+        Some(std::array::from_fn(|c| self.by_card[c][i]))
+    }
+}
+
+/// Calculates the EOR of every card on every offered bet, from a full shoe of
+/// rule.number_of_decks decks.
+pub fn calculate(rule: &Rule) -> Result<EffectOfRemoval, Error> {
+    let bets = bet_sizing::get_offered_bets(rule);
+    let counter = Counter::new(rule.number_of_decks);
+    let ex = get_exs(rule, &counter, &bets)?;
+
+    let mut by_card = Vec::with_capacity(52);
+    for c in 0..52 {
+        let mut counter = counter.clone();
+        counter.try_remove_card(Card::from_index(c))?;
+        let ex_removed = get_exs(rule, &counter, &bets)?;
+        by_card.push(ex_removed.iter().zip(&ex).map(|(x, y)| x - y).collect());
+    }
+    // Card c is of rank c % 13 + 1. Any suit will do.
+    let by_rank = by_card[..13].to_vec();
+
+    Ok(EffectOfRemoval {
+        bets,
+        ex,
+        by_rank,
+        by_card,
+    })
+}
+
+fn get_exs(rule: &Rule, counter: &Counter, bets: &[HandsBet]) -> Result<Vec<f64>, Error> {
+    let mut solution = Solution::default();
+    functional::try_calculate(rule, &mut counter.clone(), &mut solution)?;
+    Ok(bets
+        .iter()
+        .map(|bet| solution.get_ex(*bet).unwrap())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculation::tests::get_typical_rule;
+
+    #[test]
+    fn test_effect_of_removal() {
+        let mut rule = get_typical_rule();
+        rule.number_of_decks = 1;
+        let eor = calculate(&rule).unwrap();
+
+        // Removing a random card doesn't change the ex, so the EORs of all
+        // cards cancel out.
+        for i in 0..eor.bets.len() {
+            let sum: f64 = eor.by_card.iter().map(|x| x[i]).sum();
+            assert!(sum.abs() < 1e-9, "{:?}: {}", eor.bets[i], sum);
+        }
+
+        // Small cards are good for Player, and big cards for Banker.
+        let player = eor.get_by_rank(HandsBet::PlayerWin).unwrap();
+        let banker = eor.get_by_rank(HandsBet::BankerWin).unwrap();
+        assert!(player[3] < 0.0 && banker[3] > 0.0);
+        assert!(player[8] > 0.0 && banker[8] < 0.0);
+
+        // Suits don't matter from a full shoe.
+        let by_card = eor.get_by_card(HandsBet::PerfectPair).unwrap();
+        for c in 0..52 {
+            assert!((by_card[c] - by_card[c % 13]).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,413 @@
+//! Exact probabilities as big rationals, available with the `exact` feature.
+//!
+//! The floating-point solver accumulates millions of tiny products, so its
+//! results are only accurate to about 1e-12. This module computes the same
+//! probabilities without any rounding, which is slower but suitable for
+//! publishing house edge tables, or checking the floating-point solver.
+
+use super::{
+    functional,
+    incremental::{falling_factorial, IncrementalSolver, NUMBER_OF_OUTCOMES},
+    Counter,
+};
+use crate::{game::RoundOutcome, Error, Rule};
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{One, Zero};
+
+/// The exact counterpart of the probabilities and expectations in Solution.
+/// Variances and skewnesses are left out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExactSolution {
+    pub p_incomplete: BigRational,
+
+    pub p_player_win: BigRational,
+    pub ex_player_win: BigRational,
+    pub p_banker_win: BigRational,
+    pub p_banker_win_by_total: [[BigRational; 10]; 2], // [banker_cards - 2][banker_total]
+    pub ex_banker_win: BigRational,
+    pub p_tie: BigRational,
+    pub ex_tie: BigRational,
+    pub p_tie_on_total: [BigRational; 10],
+    pub ex_tie_on_total: Option<[BigRational; 10]>, // Only available when tie on total is offered.
+
+    pub p_unsuit_pair: BigRational,
+    pub ex_unsuit_pair: BigRational,
+    pub p_either_pair: BigRational,
+    pub ex_either_pair: BigRational,
+    pub p_suit_pair: [BigRational; 2],
+    pub ex_suit_pair: BigRational,
+
+    pub p_player_bonus_unnatural: [BigRational; 6],
+    pub p_player_bonus_natural_win: BigRational,
+    pub p_banker_bonus_unnatural: [BigRational; 6],
+    pub p_banker_bonus_natural_win: BigRational,
+    pub p_bonus_natural_tie: BigRational,
+    pub ex_player_bonus: BigRational,
+    pub ex_banker_bonus: BigRational,
+
+    pub sol_ez: Option<ExactSolutionEz>, // Only available in EZ Baccarat.
+    pub sol_super_6: Option<ExactSolutionSuper6>, // Only available when Super 6 is offered.
+    pub sol_big_small: Option<ExactSolutionBigSmall>, // Only available when Big/Small is offered.
+    pub sol_side_bets: Vec<ExactSolutionSideBet>,
+
+    pub outcomes: ExactOutcomeDistribution,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExactSolutionEz {
+    pub p_dragon_7: BigRational,
+    pub ex_dragon_7: BigRational,
+    pub p_panda_8: BigRational,
+    pub ex_panda_8: BigRational,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExactSolutionSuper6 {
+    pub p_super_6: [BigRational; 2],
+    pub ex_super_6: BigRational,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExactSolutionBigSmall {
+    pub p_big: BigRational,
+    pub ex_big: BigRational,
+    pub p_small: BigRational,
+    pub ex_small: BigRational,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExactSolutionSideBet {
+    pub name: String,
+    pub p: Vec<BigRational>, // p[i] stands for the probability that outcome i decides the bet.
+    pub ex: BigRational,
+}
+
+/// The exact counterpart of OutcomeDistribution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExactOutcomeDistribution {
+    /// Indexed like the flattened OutcomeDistribution::p.
+    p: Vec<BigRational>,
+
+    pub p_incomplete: BigRational,
+}
+
+/// Solves counters exactly. It keeps the enumerated rounds between solves, so
+/// reuse it rather than creating one for each counter.
+///
+/// Chemin de fer isn't supported, since its optimal strategies are found
+/// numerically.
+#[derive(Debug, Clone)]
+pub struct ExactSolver<'a> {
+    rule: &'a Rule,
+    solver: IncrementalSolver<'a>,
+}
+
+impl<'a> ExactSolver<'a> {
+    pub fn new(rule: &'a Rule) -> Self {
+        Self {
+            rule,
+            solver: IncrementalSolver::new(rule, &Counter::new(0)),
+        }
+    }
+
+    pub fn solve(&mut self, counter: &Counter) -> Result<ExactSolution, Error> {
+        if self.rule.chemin_de_fer {
+            return Err(Error::Unsupported("exact solution of Chemin de fer"));
+        }
+        functional::check_counter(counter)?;
+        self.solver.set_counter(counter);
+
+        let total = counter.total;
+        let mut p = vec![BigRational::zero(); NUMBER_OF_OUTCOMES];
+        for (sums, cards) in self.solver.get_sums().iter().zip(4..) {
+            if total < cards {
+                continue;
+            }
+            let rounds = BigInt::from(falling_factorial(total, cards as u8));
+            for (p, &sum) in p.iter_mut().zip(sums) {
+                *p += BigRational::new(sum.into(), rounds.clone());
+            }
+        }
+        let p_complete: BigRational = p.iter().sum();
+        let outcomes = ExactOutcomeDistribution {
+            p,
+            p_incomplete: BigRational::one() - p_complete,
+        };
+
+        let mut solution = ExactSolution::default();
+        calculate_outcome_bets(self.rule, &outcomes, &mut solution);
+        calculate_pair_bets(self.rule, counter, &mut solution);
+        solution.p_incomplete = outcomes.p_incomplete.clone();
+        solution.outcomes = outcomes;
+        Ok(solution)
+    }
+}
+
+/// Solves a single counter exactly. See ExactSolver.
+pub fn calculate(rule: &Rule, counter: &Counter) -> Result<ExactSolution, Error> {
+    ExactSolver::new(rule).solve(counter)
+}
+
+impl Default for ExactOutcomeDistribution {
+    fn default() -> Self {
+        Self {
+            p: vec![BigRational::zero(); NUMBER_OF_OUTCOMES],
+            p_incomplete: BigRational::zero(),
+        }
+    }
+}
+
+impl ExactOutcomeDistribution {
+    pub fn get_p(&self, outcome: &RoundOutcome) -> &BigRational {
+        &self.p[index_of(outcome)]
+    }
+
+    /// Returns the probability that the final outcome satisfies the predicate.
+    pub fn get_p_where<F: Fn(&RoundOutcome) -> bool>(&self, predicate: F) -> BigRational {
+        self.iter()
+            .filter(|(outcome, _)| predicate(outcome))
+            .map(|(_, p)| p)
+            .sum()
+    }
+
+    /// Iterates over all final outcomes (including impossible ones, whose
+    /// probability is 0) and their probabilities.
+    pub fn iter(&self) -> impl Iterator<Item = (RoundOutcome, &BigRational)> + '_ {
+        self.p.iter().enumerate().map(|(i, p)| {
+            let outcome = RoundOutcome {
+                player_total: (i / 10 % 10) as u8,
+                banker_total: (i % 10) as u8,
+                player_cards: (i / 200) as u8 + 2,
+                banker_cards: (i / 100 % 2) as u8 + 2,
+            };
+            (outcome, p)
+        })
+    }
+}
+
+/// Converts a payout to a rational by its decimal representation, so that
+/// e.g. 0.95 becomes exactly 19/20 rather than the nearest binary fraction.
+pub fn payout_to_rational(payout: f64) -> BigRational {
+    let s = payout.to_string();
+    match s.split_once('.') {
+        Some((int, frac)) => BigRational::new(
+            format!("{}{}", int, frac).parse().unwrap(),
+            BigInt::from(10).pow(frac.len() as u32),
+        ),
+        None => BigRational::from_integer(s.parse().unwrap()),
+    }
+}
+
+fn index_of(outcome: &RoundOutcome) -> usize {
+    ((outcome.player_cards as usize - 2) * 2 + outcome.banker_cards as usize - 2) * 100
+        + outcome.player_total as usize * 10
+        + outcome.banker_total as usize
+}
+
+/// Calculates the bets decided by the final outcome, i.e. all but pair bets,
+/// in the same way as Solution does.
+fn calculate_outcome_bets(rule: &Rule, outcomes: &ExactOutcomeDistribution, s: &mut ExactSolution) {
+    let payouts = &rule.payouts;
+    if rule.ez_baccarat {
+        s.sol_ez = Some(Default::default());
+    }
+    if payouts.super_6.is_some() {
+        s.sol_super_6 = Some(Default::default());
+    }
+    if payouts.big_small.is_some() {
+        s.sol_big_small = Some(Default::default());
+    }
+    s.sol_side_bets = rule
+        .side_bets
+        .iter()
+        .map(|side_bet| ExactSolutionSideBet {
+            name: side_bet.name.clone(),
+            p: vec![BigRational::zero(); side_bet.outcomes.len()],
+            ex: BigRational::zero(),
+        })
+        .collect();
+
+    for (outcome, p) in outcomes.iter() {
+        if p.is_zero() {
+            continue;
+        }
+        add_p_of_outcome(outcome, p, s);
+        for (s, side_bet) in s.sol_side_bets.iter_mut().zip(&rule.side_bets) {
+            if let Some(i) = side_bet.settle(&outcome) {
+                s.p[i] += p;
+            }
+        }
+    }
+
+    // A void round is neither a win nor a loss.
+    let p_complete = BigRational::one() - &outcomes.p_incomplete;
+    // The ex of a bet paying payouts[i] with probabilities p[i] and losing
+    // otherwise.
+    let get_ex = |wins: &[(&BigRational, f64)]| -> BigRational {
+        let mut ex = BigRational::zero();
+        let mut p_lose = p_complete.clone();
+        for &(p, payout) in wins {
+            ex += p * payout_to_rational(payout);
+            p_lose -= p;
+        }
+        ex - p_lose
+    };
+
+    s.ex_player_win = &s.p_player_win * payout_to_rational(payouts.player_win) - &s.p_banker_win;
+    s.ex_banker_win = -s.p_player_win.clone();
+    for (cards, p) in s.p_banker_win_by_total.iter().enumerate() {
+        for (total, p) in p.iter().enumerate() {
+            let payout = rule.get_banker_win_payout(total as u8, cards as u8 + 2);
+            s.ex_banker_win += p * payout_to_rational(payout);
+        }
+    }
+    s.ex_tie = get_ex(&[(&s.p_tie, payouts.tie)]);
+    if let Some(payout) = payouts.tie_on_total {
+        let ex = std::array::from_fn(|i| get_ex(&[(&s.p_tie_on_total[i], payout[i])]));
+        s.ex_tie_on_total = Some(ex);
+    }
+
+    let bonus = |natural_win: &BigRational, unnatural: &[BigRational; 6]| {
+        let mut wins = vec![
+            (natural_win, payouts.bonus_natural_win),
+            (&s.p_bonus_natural_tie, payouts.bonus_natural_tie),
+        ];
+        wins.extend(unnatural.iter().zip(payouts.bonus_unnatural));
+        get_ex(&wins)
+    };
+    s.ex_player_bonus = bonus(&s.p_player_bonus_natural_win, &s.p_player_bonus_unnatural);
+    s.ex_banker_bonus = bonus(&s.p_banker_bonus_natural_win, &s.p_banker_bonus_unnatural);
+
+    if let Some(s) = &mut s.sol_ez {
+        s.ex_dragon_7 = get_ex(&[(&s.p_dragon_7, payouts.dragon_7)]);
+        s.ex_panda_8 = get_ex(&[(&s.p_panda_8, payouts.panda_8)]);
+    }
+    if let (Some(s), Some(payout)) = (&mut s.sol_super_6, payouts.super_6) {
+        s.ex_super_6 = get_ex(&[(&s.p_super_6[0], payout[0]), (&s.p_super_6[1], payout[1])]);
+    }
+    if let (Some(s), Some(payout)) = (&mut s.sol_big_small, payouts.big_small) {
+        s.ex_big = &s.p_big * payout_to_rational(payout[0]) - &s.p_small;
+        s.ex_small = &s.p_small * payout_to_rational(payout[1]) - &s.p_big;
+    }
+    for (s, side_bet) in s.sol_side_bets.iter_mut().zip(&rule.side_bets) {
+        let wins: Vec<_> =
+            s.p.iter()
+                .zip(&side_bet.outcomes)
+                .map(|(p, o)| (p, o.payout))
+                .collect();
+        s.ex = get_ex(&wins);
+    }
+}
+
+/// The exact counterpart of Solution::add_p_of_outcome_to_solution.
+fn add_p_of_outcome(outcome: RoundOutcome, p: &BigRational, s: &mut ExactSolution) {
+    if let Some(s) = &mut s.sol_big_small {
+        if outcome.player_cards + outcome.banker_cards == 4 {
+            s.p_small += p;
+        } else {
+            s.p_big += p;
+        }
+    }
+
+    let (player_sum, banker_sum) = (outcome.player_total, outcome.banker_total);
+    match player_sum.cmp(&banker_sum) {
+        std::cmp::Ordering::Less => {
+            s.p_banker_win += p;
+            s.p_banker_win_by_total[outcome.banker_cards as usize - 2][banker_sum as usize] += p;
+            let delta = (banker_sum - player_sum) as usize;
+            if outcome.is_banker_natural() {
+                s.p_banker_bonus_natural_win += p;
+            } else if delta >= 4 {
+                s.p_banker_bonus_unnatural[delta - 4] += p;
+            }
+            if let Some(ez) = &mut s.sol_ez {
+                if outcome.banker_cards == 3 && banker_sum == 7 {
+                    ez.p_dragon_7 += p;
+                }
+            }
+            if let Some(super_6) = &mut s.sol_super_6 {
+                if banker_sum == 6 {
+                    super_6.p_super_6[outcome.banker_cards as usize - 2] += p;
+                }
+            }
+        }
+        std::cmp::Ordering::Equal => {
+            s.p_tie += p;
+            s.p_tie_on_total[player_sum as usize] += p;
+            if outcome.is_player_natural() {
+                s.p_bonus_natural_tie += p;
+            }
+        }
+        std::cmp::Ordering::Greater => {
+            s.p_player_win += p;
+            let delta = (player_sum - banker_sum) as usize;
+            if outcome.is_player_natural() {
+                s.p_player_bonus_natural_win += p;
+            } else if delta >= 4 {
+                s.p_player_bonus_unnatural[delta - 4] += p;
+            }
+            if let Some(ez) = &mut s.sol_ez {
+                if outcome.player_cards == 3 && player_sum == 8 {
+                    ez.p_panda_8 += p;
+                }
+            }
+        }
+    }
+}
+
+fn calculate_pair_bets(rule: &Rule, counter: &Counter, s: &mut ExactSolution) {
+    let total = counter.total;
+    let pairs = |count: u32| BigInt::from(count) * BigInt::from(count.saturating_sub(1));
+    let total_pairs = pairs(total);
+
+    let unsuit_pairs: BigInt = counter.value_count.iter().map(|&c| pairs(c)).sum();
+    s.p_unsuit_pair = BigRational::new(unsuit_pairs, total_pairs.clone());
+
+    // Either pair = 1 - P(neither hand is a pair).
+    let mut neither_pair = BigInt::zero();
+    let mut value_count = counter.value_count;
+    for i in 0..13 {
+        for j in 0..13 {
+            if i == j || value_count[i] == 0 || value_count[j] == 0 {
+                continue;
+            }
+            // Player's cards are of values i and j, in this order.
+            let first = BigInt::from(value_count[i]) * BigInt::from(value_count[j]);
+            value_count[i] -= 1;
+            value_count[j] -= 1;
+            let rest_pairs: BigInt = value_count.iter().map(|&c| pairs(c)).sum();
+            neither_pair += first * (pairs(total - 2) - rest_pairs);
+            value_count[i] += 1;
+            value_count[j] += 1;
+        }
+    }
+    s.p_either_pair =
+        BigRational::one() - BigRational::new(neither_pair, pairs(total) * pairs(total - 2));
+
+    let mut card_count = counter.card_count;
+    for i in 0..52 {
+        if card_count[i] <= 1 {
+            continue;
+        }
+        let p_first_pair = BigRational::new(pairs(card_count[i]), total_pairs.clone());
+        card_count[i] -= 2;
+        let second_pairs: BigInt = card_count.iter().map(|&c| pairs(c)).sum();
+        let p_second_pair = BigRational::new(second_pairs, pairs(total - 2));
+        card_count[i] += 2;
+
+        s.p_suit_pair[0] += &p_first_pair * (BigRational::one() - &p_second_pair);
+        s.p_suit_pair[1] += p_first_pair * p_second_pair;
+    }
+    s.p_suit_pair[0] *= BigRational::from_integer(2.into());
+
+    let payouts = &rule.payouts;
+    let lose = |p: &BigRational| BigRational::one() - p;
+    s.ex_unsuit_pair =
+        &s.p_unsuit_pair * payout_to_rational(payouts.unsuit_pair) - lose(&s.p_unsuit_pair);
+    s.ex_either_pair =
+        &s.p_either_pair * payout_to_rational(payouts.either_pair) - lose(&s.p_either_pair);
+    s.ex_suit_pair = &s.p_suit_pair[0] * payout_to_rational(payouts.perfect_pair[0])
+        + &s.p_suit_pair[1] * payout_to_rational(payouts.perfect_pair[1])
+        - (BigRational::one() - &s.p_suit_pair[0] - &s.p_suit_pair[1]);
+}
@@ -0,0 +1,283 @@
+use super::{functional, Counter, OutcomeDistribution, Solution, MAX_CARDS_PER_ROUND};
+use crate::{card::Card, game::SolverProvider, Error, Rule};
+use std::collections::HashMap;
+
+pub(crate) const NUMBER_OF_OUTCOMES: usize = 2 * 2 * 10 * 10;
+
+/// (outcome, number of ordered rounds), kept small since every update of a
+/// term goes through its outcomes.
+type OutcomeRounds = Vec<(u16, u32)>;
+
+/// A solver that keeps track of a counter, and updates the distribution of the
+/// final outcomes when a single card is removed from or added to it, instead of
+/// enumerating all the rounds again.
+///
+/// A round that deals m cards, k[v] of which have baccarat value v, happens
+/// with probability prod((n[v])_(k[v])) / (total)_m, where n[v] is the count of
+/// value v in the shoe and (x)_k is the falling factorial. So the rounds are
+/// grouped by the values they use, and only the groups using the value of the
+/// changed card are updated. The numerators are kept as exact integers, so the
+/// result doesn't drift however many updates are made.
+///
+/// Chemin de fer isn't supported incrementally, since the optimal strategy
+/// changes with the counter. Every solve is a full calculation then.
+#[derive(Debug, Clone)]
+pub struct IncrementalSolver<'a> {
+    rule: &'a Rule,
+    counter: Counter,
+    terms: Vec<Term>,
+    outcome_rounds: OutcomeRounds,
+    terms_with_value: [Vec<usize>; 10],
+    falling: [[u128; MAX_CARDS_PER_ROUND as usize + 1]; 10], // (count of value v)_k
+    sums: [[u128; NUMBER_OF_OUTCOMES]; 3],                   // [cards - 4][outcome]
+    solution: Solution,
+    outcomes: OutcomeDistribution,
+}
+
+/// All the rounds that deal the same values, regardless of order.
+#[derive(Debug, Clone)]
+struct Term {
+    values: Vec<(usize, u8)>, // (value, number of cards of that value dealt)
+    cards: usize,
+    weight: u128,
+    outcomes: std::ops::Range<usize>, // Range in IncrementalSolver::outcome_rounds.
+}
+
+impl<'a> IncrementalSolver<'a> {
+    pub fn new(rule: &'a Rule, counter: &Counter) -> Self {
+        let (terms, outcome_rounds) = build_terms(rule);
+        let mut terms_with_value: [Vec<usize>; 10] = Default::default();
+        for (i, term) in terms.iter().enumerate() {
+            for &(v, _) in &term.values {
+                terms_with_value[v].push(i);
+            }
+        }
+
+        let mut solver = Self {
+            rule,
+            counter: counter.clone(),
+            terms,
+            outcome_rounds,
+            terms_with_value,
+            falling: [[0; MAX_CARDS_PER_ROUND as usize + 1]; 10],
+            sums: [[0; NUMBER_OF_OUTCOMES]; 3],
+            solution: Default::default(),
+            outcomes: Default::default(),
+        };
+        for v in 0..10 {
+            solver.update_falling(v);
+        }
+        for i in 0..solver.terms.len() {
+            solver.update_term(i);
+        }
+        solver
+    }
+
+    pub fn get_counter(&self) -> &Counter {
+        &self.counter
+    }
+
+    pub fn remove_card(&mut self, card: Card) -> Result<(), Error> {
+        self.counter.try_remove_card(card)?;
+        self.update_value(card.to_bcr_value_index());
+        Ok(())
+    }
+
+    pub fn add_card(&mut self, card: Card) {
+        self.counter.add_card(card);
+        self.update_value(card.to_bcr_value_index());
+    }
+
+    /// Returns the solution of the tracked counter.
+    pub fn get_solution(&mut self) -> &Solution {
+        self.solution = Default::default();
+        if self.rule.chemin_de_fer {
+            functional::calculate(self.rule, &mut self.counter, &mut self.solution);
+            return &self.solution;
+        }
+
+        self.outcomes = Default::default();
+        let total = self.counter.total;
+        for (sums, cards) in self.sums.iter().zip(4..) {
+            if total < cards {
+                continue;
+            }
+            let rounds = falling_factorial(total, cards as u8) as f64;
+            let p = self.outcomes.p.iter_mut().flatten().flatten().flatten();
+            for (p, &sum) in p.zip(sums) {
+                *p += sum as f64 / rounds;
+            }
+        }
+        if total < MAX_CARDS_PER_ROUND {
+            let p_complete: f64 = self.outcomes.iter().map(|(_, p)| p).sum();
+            self.outcomes.p_incomplete = (1.0 - p_complete).max(0.0);
+        }
+
+        functional::calculate_based_on_outcomes(
+            self.rule,
+            &mut self.counter,
+            &self.outcomes,
+            &mut self.solution,
+        );
+        &self.solution
+    }
+
+    /// Makes the tracked counter the same as the given one, only updating the
+    /// values whose counts differ.
+    pub fn set_counter(&mut self, counter: &Counter) {
+        let old_counts = self.counter.bcr_value_count;
+        self.counter = counter.clone();
+        let changed: Vec<usize> = (0..10)
+            .filter(|&v| old_counts[v] != counter.bcr_value_count[v])
+            .collect();
+        // With all the falling factorials up to date first, a term using
+        // several changed values gets its final weight on the first update,
+        // and is skipped on the others.
+        for &v in &changed {
+            self.update_falling(v);
+        }
+        for v in changed {
+            for j in 0..self.terms_with_value[v].len() {
+                self.update_term(self.terms_with_value[v][j]);
+            }
+        }
+    }
+
+    /// The same as set_counter(), but also returns the solution.
+    pub fn solve(&mut self, counter: &Counter) -> &Solution {
+        self.set_counter(counter);
+        self.get_solution()
+    }
+
+    /// sums[m - 4][outcome] is the number of ordered ways to deal m cards from
+    /// the tracked counter that end with the outcome, which is indexed like
+    /// the flattened OutcomeDistribution::p. Chemin de fer options are drawn
+    /// according to the tableau.
+    #[cfg(feature = "exact")]
+    pub(crate) fn get_sums(&self) -> &[[u128; NUMBER_OF_OUTCOMES]; 3] {
+        &self.sums
+    }
+
+    fn update_value(&mut self, value: usize) {
+        self.update_falling(value);
+        for j in 0..self.terms_with_value[value].len() {
+            self.update_term(self.terms_with_value[value][j]);
+        }
+    }
+
+    fn update_falling(&mut self, value: usize) {
+        let n = self.counter.bcr_value_count[value];
+        for (k, falling) in self.falling[value].iter_mut().enumerate() {
+            *falling = falling_factorial(n, k as u8);
+        }
+    }
+
+    fn update_term(&mut self, i: usize) {
+        let term = &mut self.terms[i];
+        let weight: u128 = term
+            .values
+            .iter()
+            .map(|&(v, k)| self.falling[v][k as usize])
+            .product();
+        if weight == term.weight {
+            return;
+        }
+
+        // The sums may go "negative" in between, but they're exact modulo
+        // 2^128 and end up non-negative, so wrapping arithmetic is fine.
+        let delta = weight.wrapping_sub(term.weight);
+        let sums = &mut self.sums[term.cards - 4];
+        for &(outcome, rounds) in &self.outcome_rounds[term.outcomes.clone()] {
+            let sum = &mut sums[outcome as usize];
+            *sum = sum.wrapping_add((rounds as u128).wrapping_mul(delta));
+        }
+        term.weight = weight;
+    }
+}
+
+impl<'a> SolverProvider for IncrementalSolver<'a> {
+    fn solve(&mut self, counter: &Counter) -> &Solution {
+        IncrementalSolver::solve(self, counter)
+    }
+}
+
+pub(crate) fn falling_factorial(n: u32, k: u8) -> u128 {
+    (0..k as u32).map(|i| n.saturating_sub(i) as u128).product()
+}
+
+/// Enumerates all the ordered rounds (regardless of the counter), and groups
+/// them by the values they deal. Returns the terms, and the outcomes of all
+/// the terms laid out contiguously.
+fn build_terms(rule: &Rule) -> (Vec<Term>, OutcomeRounds) {
+    let tableau = &rule.tableau;
+    let mut grouped: Vec<([u8; 10], usize, OutcomeRounds)> = vec![];
+    let mut term_index = HashMap::new();
+    let mut outcome_index = HashMap::new();
+    let mut add = |cards: &[usize], player_cards: usize, banker_cards: usize| {
+        let mut values = [0u8; 10];
+        for &card in cards {
+            values[card] += 1;
+        }
+        let player_total = cards[..player_cards].iter().sum::<usize>() % 10;
+        let banker_total = cards[player_cards..].iter().sum::<usize>() % 10;
+        let outcome =
+            ((player_cards - 2) * 2 + banker_cards - 2) * 100 + player_total * 10 + banker_total;
+
+        let i = *term_index.entry(values).or_insert_with(|| {
+            grouped.push((values, cards.len(), vec![]));
+            grouped.len() - 1
+        });
+        let j = *outcome_index.entry((i, outcome)).or_insert_with(|| {
+            grouped[i].2.push((outcome as u16, 0));
+            grouped[i].2.len() - 1
+        });
+        grouped[i].2[j].1 += 1;
+    };
+
+    for p0 in 0..=9 {
+        for p1 in 0..=9 {
+            for b0 in 0..=9 {
+                for b1 in 0..=9 {
+                    let player_sum = (p0 + p1) % 10;
+                    let banker_sum = (b0 + b1) % 10;
+                    if player_sum >= 8 || banker_sum >= 8 {
+                        add(&[p0, p1, b0, b1], 2, 2);
+                    } else if tableau.should_player_draw(player_sum as u8) {
+                        for p2 in 0..=9 {
+                            if tableau.should_banker_draw(banker_sum as u8, Some(p2 as u8)) {
+                                for b2 in 0..=9 {
+                                    add(&[p0, p1, p2, b0, b1, b2], 3, 3);
+                                }
+                            } else {
+                                add(&[p0, p1, p2, b0, b1], 3, 2);
+                            }
+                        }
+                    } else if tableau.should_banker_draw(banker_sum as u8, None) {
+                        for b2 in 0..=9 {
+                            add(&[p0, p1, b0, b1, b2], 2, 3);
+                        }
+                    } else {
+                        add(&[p0, p1, b0, b1], 2, 2);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut terms = Vec::with_capacity(grouped.len());
+    let mut outcome_rounds = vec![];
+    for (values, cards, outcomes) in grouped {
+        let start = outcome_rounds.len();
+        outcome_rounds.extend(outcomes);
+        terms.push(Term {
+            values: (0..10)
+                .filter(|&v| values[v] > 0)
+                .map(|v| (v, values[v]))
+                .collect(),
+            cards,
+            weight: 0,
+            outcomes: start..outcome_rounds.len(),
+        });
+    }
+    (terms, outcome_rounds)
+}
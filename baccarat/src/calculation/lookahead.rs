@@ -0,0 +1,382 @@
+//! Looking ahead over the next rounds of the shoe, e.g. to see how much the
+//! rest of a shoe is worth before leaving the table.
+//!
+//! Betting doesn't change which cards are dealt, so the optimal policy of
+//! betting 1 unit or not in each round is simply to bet on the best bet
+//! whenever its ex is positive. The value of the next rounds is then the
+//! expectation of the sum of max(0, best ex) over them, where the ex of every
+//! later round depends on the cards dealt before it.
+//!
+//! The bets only care about the values of the cards, and whether two cards
+//! have the same rank or are the same card (for pair bets). So shoes that only
+//! differ by relabelling the suits of a rank, or by swapping 10, J, Q and K
+//! around, are worth the same, and the exact calculation only enumerates one
+//! card of each group of interchangeable cards. It still branches over the
+//! ranks of the cards, so with the default max_deals it's only exact for
+//! about the last 20 cards of a real shoe when looking 1 round ahead, or the
+//! last 12 cards for more rounds, and samples before that.
+
+use super::{functional, incremental::IncrementalSolver, Counter, MIN_CARDS_PER_ROUND};
+use crate::{bet_sizing, card::Card, game::HandsBet, tableau::Tableau, Error, Rule};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The number of cards the exact calculation may deal (summed over all the
+/// rounds it enumerates) before it gives up and samples instead.
+pub const DEFAULT_MAX_DEALS: u64 = 1_000_000;
+/// The number of sampled shoe continuations when the exact calculation is too
+/// expensive.
+pub const DEFAULT_SAMPLES: u32 = 1000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct Lookahead {
+    /// The best bet of the current round, or None if no bet has positive ex.
+    pub bet: Option<HandsBet>,
+    /// The ex of the best bet of the current round.
+    pub ex: f64,
+    /// The expected total profit over the rounds, betting 1 unit on the best
+    /// bet whenever its ex is positive.
+    pub value: f64,
+    /// The part of value from the rounds after the current one.
+    pub value_later: f64,
+    /// Whether value is calculated exactly, or estimated by sampling.
+    pub exact: bool,
+    /// The standard error of value if it's sampled, or 0.
+    pub std_error: f64,
+}
+
+/// Solves the value of the next rounds by dynamic programming over the cards
+/// removed by each round (up to interchangeable cards), or by sampling if
+/// there are too many cards to enumerate.
+///
+/// Chemin de fer isn't supported, since the cards dealt depend on the
+/// decisions.
+#[derive(Debug, Clone)]
+pub struct LookaheadSolver<'a> {
+    rule: &'a Rule,
+    solver: IncrementalSolver<'a>,
+    bets: Vec<HandsBet>,
+    max_deals: u64,
+    samples: u32,
+    memo: HashMap<(Counter, u32), f64>,
+}
+
+impl<'a> LookaheadSolver<'a> {
+    pub fn new(rule: &'a Rule) -> Self {
+        Self::with_limits(rule, DEFAULT_MAX_DEALS, DEFAULT_SAMPLES)
+    }
+
+    /// max_deals limits the work of the exact calculation, and samples is the
+    /// number of samples when it's exceeded.
+    pub fn with_limits(rule: &'a Rule, max_deals: u64, samples: u32) -> Self {
+        Self {
+            rule,
+            solver: IncrementalSolver::new(rule, &Counter::new(0)),
+            bets: bet_sizing::get_offered_bets(rule),
+            max_deals,
+            samples,
+            memo: HashMap::new(),
+        }
+    }
+
+    /// Looks ahead over the given number of rounds (including the current
+    /// one) from the counter. Fewer rounds are played if the shoe runs out of
+    /// cards.
+    pub fn solve(&mut self, counter: &Counter, rounds: u32) -> Result<Lookahead, Error> {
+        if self.rule.chemin_de_fer {
+            return Err(Error::Unsupported("lookahead of Chemin de fer"));
+        }
+        functional::check_counter(counter)?;
+
+        let (bet, ex) = self.get_best_bet(counter);
+        let mut lookahead = Lookahead {
+            bet: (ex > 0.0).then_some(bet),
+            ex,
+            exact: true,
+            ..Default::default()
+        };
+        if rounds == 0 {
+            return Ok(lookahead);
+        }
+
+        let mut deals = self.max_deals;
+        let exact = self.get_exact_value(&canonicalize(counter), rounds, &mut deals);
+        self.memo.clear();
+        match exact {
+            Some(value) => lookahead.value = value,
+            None => {
+                let (value, std_error) = self.get_sampled_value(counter, rounds);
+                lookahead.value = value;
+                lookahead.exact = false;
+                lookahead.std_error = std_error;
+            }
+        }
+        lookahead.value_later = lookahead.value - ex.max(0.0);
+        Ok(lookahead)
+    }
+
+    fn get_best_bet(&mut self, counter: &Counter) -> (HandsBet, f64) {
+        let solution = self.solver.solve(counter);
+        self.bets
+            .iter()
+            .map(|bet| (*bet, solution.get_ex(*bet).unwrap()))
+            .max_by(|x, y| x.1.total_cmp(&y.1))
+            .unwrap()
+    }
+
+    /// The counter must be canonical. Returns None if more than deals cards
+    /// would be dealt.
+    fn get_exact_value(&mut self, counter: &Counter, rounds: u32, deals: &mut u64) -> Option<f64> {
+        if rounds == 0 || counter.total < MIN_CARDS_PER_ROUND {
+            return Some(0.0);
+        }
+        if let Some(value) = self.memo.get(&(counter.clone(), rounds)) {
+            return Some(*value);
+        }
+
+        let mut value = self.get_best_bet(counter).1.max(0.0);
+        let mut nexts = HashMap::new();
+        let mut cards = Vec::with_capacity(6);
+        let mut shoe = counter.clone();
+        enumerate_rounds(
+            &self.rule.tableau,
+            &mut shoe,
+            &mut cards,
+            1.0,
+            &mut nexts,
+            deals,
+        )?;
+        for (next, p) in nexts {
+            value += p * self.get_exact_value(&next, rounds - 1, deals)?;
+        }
+        self.memo.insert((counter.clone(), rounds), value);
+        Some(value)
+    }
+
+    /// Returns the mean and its standard error.
+    fn get_sampled_value(&mut self, counter: &Counter, rounds: u32) -> (f64, f64) {
+        let mut rng = rand::thread_rng();
+        let (mut sum, mut sum_squared) = (0.0, 0.0);
+        for _ in 0..self.samples {
+            let mut shoe = counter.clone();
+            let mut value = 0.0;
+            for _ in 0..rounds {
+                if shoe.total < MIN_CARDS_PER_ROUND {
+                    break;
+                }
+                value += self.get_best_bet(&shoe).1.max(0.0);
+                if !deal_random_round(&self.rule.tableau, &mut shoe, &mut rng) {
+                    break;
+                }
+            }
+            sum += value;
+            sum_squared += value * value;
+        }
+        let n = self.samples.max(1) as f64;
+        let mean = sum / n;
+        let variance = (sum_squared / n - mean * mean).max(0.0);
+        (mean, (variance / n).sqrt())
+    }
+}
+
+/// Whether the round needs another card, given the cards dealt so far in the
+/// order of dealing, i.e. player, banker, player, banker and the third cards.
+fn needs_card(tableau: &Tableau, cards: &[Card]) -> bool {
+    if cards.len() < 4 {
+        return true;
+    }
+    let value = |i: usize| cards[i].to_bcr_value_index() as u8;
+    let player_total = (value(0) + value(2)) % 10;
+    let banker_total = (value(1) + value(3)) % 10;
+    if player_total >= 8 || banker_total >= 8 {
+        return false;
+    }
+    let player_draws = tableau.should_player_draw(player_total);
+    match cards.len() {
+        4 => player_draws || tableau.should_banker_draw(banker_total, None),
+        5 if player_draws => tableau.should_banker_draw(banker_total, Some(value(4))),
+        _ => false,
+    }
+}
+
+/// Returns the canonical one of the counters that are the same up to
+/// relabelling the suits of a rank, or swapping the ranks of value 0 (10, J, Q
+/// and K). They all have the same solution and the same next rounds.
+fn canonicalize(counter: &Counter) -> Counter {
+    let mut ranks = [[0; 4]; 13];
+    for (rank, suits) in ranks.iter_mut().enumerate() {
+        for (suit, count) in suits.iter_mut().enumerate() {
+            *count = counter.card_count[suit * 13 + rank];
+        }
+        suits.sort_unstable_by(|x, y| y.cmp(x));
+    }
+    ranks[9..].sort_unstable_by(|x, y| y.cmp(x));
+
+    let mut card_count = [0; 52];
+    for (rank, suits) in ranks.iter().enumerate() {
+        for (suit, count) in suits.iter().enumerate() {
+            card_count[suit * 13 + rank] = *count;
+        }
+    }
+    Counter::with_card_count(&card_count)
+}
+
+/// Groups the cards in the shoe which are interchangeable (see
+/// canonicalize()), i.e. those of the same count, and whose ranks are the same,
+/// or of value 0 with the same counts of suits. Returns a card of each group
+/// and the number of cards in the group.
+fn get_card_groups(shoe: &Counter) -> Vec<(Card, u32)> {
+    // The rank (9 for all of value 0), its sorted counts of suits, and the
+    // count of the card.
+    type Key = (usize, [u32; 4], u32);
+    let mut groups: Vec<(Key, Card, u32)> = Vec::with_capacity(52);
+    for rank in 0..13 {
+        let mut suits = [0; 4];
+        for (suit, count) in suits.iter_mut().enumerate() {
+            *count = shoe.card_count[suit * 13 + rank];
+        }
+        let mut sorted_suits = suits;
+        sorted_suits.sort_unstable();
+        for (suit, count) in suits.into_iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let key = (rank.min(9), sorted_suits, count);
+            match groups.iter_mut().find(|group| group.0 == key) {
+                Some(group) => group.2 += count,
+                None => groups.push((key, Card::from_index(suit * 13 + rank), count)),
+            }
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, card, count)| (card, count))
+        .collect()
+}
+
+/// Adds the canonical counters after every possible round, and their
+/// probabilities, to nexts. Rounds that run out of cards end the shoe, so
+/// they're left out. Returns None if more than deals cards would be dealt.
+fn enumerate_rounds(
+    tableau: &Tableau,
+    shoe: &mut Counter,
+    cards: &mut Vec<Card>,
+    p: f64,
+    nexts: &mut HashMap<Counter, f64>,
+    deals: &mut u64,
+) -> Option<()> {
+    if !needs_card(tableau, cards) {
+        *nexts.entry(canonicalize(shoe)).or_insert(0.0) += p;
+        return Some(());
+    }
+    let total = shoe.total as f64;
+    for (card, count) in get_card_groups(shoe) {
+        *deals = deals.checked_sub(1)?;
+        shoe.remove_card(card);
+        cards.push(card);
+        let result = enumerate_rounds(tableau, shoe, cards, p * count as f64 / total, nexts, deals);
+        cards.pop();
+        shoe.add_card(card);
+        result?;
+    }
+    Some(())
+}
+
+/// Deals a random round from the shoe. Returns false if the shoe runs out of
+/// cards in the middle of the round.
+fn deal_random_round<R: Rng>(tableau: &Tableau, shoe: &mut Counter, rng: &mut R) -> bool {
+    let mut cards = Vec::with_capacity(6);
+    while needs_card(tableau, &cards) {
+        if shoe.total == 0 {
+            return false;
+        }
+        let mut k = rng.gen_range(0..shoe.total);
+        let i = shoe
+            .card_count
+            .iter()
+            .position(|&count| {
+                if k < count {
+                    return true;
+                }
+                k -= count;
+                false
+            })
+            .unwrap();
+        let card = Card::from_index(i);
+        shoe.remove_card(card);
+        cards.push(card);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculation::tests::{assert_float_equal, get_typical_rule};
+
+    #[test]
+    fn test_lookahead() {
+        let rule = get_typical_rule();
+
+        // Only 4 cards are left, so there's exactly 1 round.
+        let mut card_count = [0; 52];
+        card_count[0] = 2;
+        card_count[9] = 2;
+        let counter = Counter::with_card_count(&card_count);
+        let mut solver = LookaheadSolver::new(&rule);
+        let lookahead = solver.solve(&counter, 3).unwrap();
+        assert!(lookahead.exact);
+        assert_eq!(lookahead.value, lookahead.ex.max(0.0));
+        assert_eq!(lookahead.value_later, 0.0);
+
+        // A small shoe is solved exactly, and sampling agrees with it.
+        let mut card_count = [0; 52];
+        for i in [0, 5, 9, 13, 20, 26, 38, 45, 51] {
+            card_count[i] = 1;
+        }
+        card_count[9] = 3;
+        let counter = Counter::with_card_count(&card_count);
+        let exact = solver.solve(&counter, 3).unwrap();
+        assert!(exact.exact);
+        assert!(exact.value >= exact.ex.max(0.0));
+        assert!(exact.value_later > 0.0);
+
+        let mut solver = LookaheadSolver::with_limits(&rule, 0, 500);
+        let sampled = solver.solve(&counter, 3).unwrap();
+        assert!(!sampled.exact);
+        assert_eq!(sampled.ex, exact.ex);
+        assert!(
+            (sampled.value - exact.value).abs() < 5.0 * sampled.std_error + 1e-9,
+            "{:?} {:?}",
+            sampled,
+            exact
+        );
+
+        // Relabelling the suits of a rank or swapping 10, J, Q and K doesn't
+        // change the value.
+        let mut relabelled = [0; 52];
+        for (i, count) in card_count.iter().enumerate() {
+            let (suit, rank) = (i / 13, i % 13);
+            let rank = match rank {
+                9 => 11,
+                11 => 9,
+                rank => rank,
+            };
+            let suit = if rank == 0 { 3 - suit } else { suit };
+            relabelled[suit * 13 + rank] += count;
+        }
+        let relabelled = Counter::with_card_count(&relabelled);
+        assert_ne!(relabelled, counter);
+        assert_eq!(canonicalize(&relabelled), canonicalize(&counter));
+        let mut solver = LookaheadSolver::new(&rule);
+        let lookahead = solver.solve(&relabelled, 3).unwrap();
+        assert!(lookahead.exact);
+        assert_float_equal(lookahead.value, exact.value);
+
+        let mut rule = get_typical_rule();
+        rule.chemin_de_fer = true;
+        let mut solver = LookaheadSolver::new(&rule);
+        assert!(solver.solve(&Counter::new(8), 1).is_err());
+    }
+}
@@ -1,3 +1,4 @@
+use crate::Error;
 use rand::seq::SliceRandom;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -26,7 +27,7 @@ impl std::fmt::Debug for Card {
         };
         let value = match self.value {
             1 => 'A',
-            v @ 2..=9 => ('0' as u8 + v) as char,
+            v @ 2..=9 => (b'0' + v) as char,
             10 => 'T',
             11 => 'J',
             12 => 'Q',
@@ -52,13 +53,17 @@ impl Card {
     }
 
     pub fn from_index(index: usize) -> Card {
+        Self::try_from_index(index).unwrap()
+    }
+
+    pub fn try_from_index(index: usize) -> Result<Card, Error> {
         if index >= 52 {
-            panic!("Index must be < 52");
+            return Err(Error::InvalidCardIndex(index));
         }
-        Card {
+        Ok(Card {
             suit: SUITS[index / 13],
             value: (index % 13 + 1) as u8,
-        }
+        })
     }
 
     pub fn to_bcr_value_index(&self) -> usize {
@@ -106,18 +111,25 @@ impl Shoe {
     }
 
     pub fn shuffle_with_firsts(&mut self, firsts: &[Card]) {
-        self.index = 0;
-        let mut card_count = [0; 52];
+        self.try_shuffle_with_firsts(firsts).unwrap();
+    }
 
-        for (i, card) in firsts.iter().enumerate() {
+    /// The same as shuffle_with_firsts(), but returns an error rather than
+    /// panics if firsts contains more cards than the shoe. The shoe is left
+    /// untouched in that case.
+    pub fn try_shuffle_with_firsts(&mut self, firsts: &[Card]) -> Result<(), Error> {
+        let mut card_count = [0; 52];
+        for card in firsts {
             let count = &mut card_count[card.to_index()];
             *count += 1;
             if *count > self.number_of_decks {
-                panic!("Invalid firsts");
+                return Err(Error::TooManyCards(*card));
             }
-            self.cards[i] = *card;
         }
 
+        self.index = 0;
+        self.cards[..firsts.len()].copy_from_slice(firsts);
+
         let mut idx = firsts.len();
         for suit in SUITS {
             for value in 1..=13 {
@@ -131,6 +143,7 @@ impl Shoe {
         }
 
         self.cards[firsts.len()..].shuffle(&mut rand::thread_rng());
+        Ok(())
     }
 
     pub fn retry_without_shuffle(&mut self) {
@@ -162,6 +175,12 @@ impl Shoe {
     pub fn is_cut_card_reached(&self) -> bool {
         self.index >= self.cut_card_index
     }
+
+    /// Returns the number of cards to deal until the cut card is reached, or
+    /// 0 if it's already reached.
+    pub fn get_cards_before_cut(&self) -> u32 {
+        self.cut_card_index.saturating_sub(self.index) as u32
+    }
 }
 
 #[cfg(test)]
@@ -226,8 +245,10 @@ mod tests {
             shoe.deal_card();
             assert!(!shoe.is_cut_card_reached());
         }
+        assert_eq!(shoe.get_cards_before_cut(), 1);
         shoe.deal_card();
         assert!(shoe.is_cut_card_reached());
+        assert_eq!(shoe.get_cards_before_cut(), 0);
 
         shoe.shuffle();
         assert_eq!(shoe.index, 0);
@@ -264,4 +285,20 @@ mod tests {
         }
         println!();
     }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(Card::try_from_index(51), Ok(Card::new(Suit::Spade, 13)));
+        assert_eq!(Card::try_from_index(52), Err(Error::InvalidCardIndex(52)));
+
+        let card = Card::new(Suit::Heart, 3);
+        let mut shoe = Shoe::new(1, 0.9);
+        let cards = shoe.cards.clone();
+        assert_eq!(
+            shoe.try_shuffle_with_firsts(&[card, card]),
+            Err(Error::TooManyCards(card))
+        );
+        assert_eq!(shoe.cards, cards);
+        assert_eq!(shoe.try_shuffle_with_firsts(&[card]), Ok(()));
+    }
 }
@@ -0,0 +1,250 @@
+use crate::{
+    calculation::{effect_of_removal::EffectOfRemoval, Counter},
+    card::Card,
+    game::HandsBet,
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+/// A linear card counting system, which adds up a tag for every card dealt.
+/// The higher the count, the better the bet it's designed for.
+///
+/// Tags are given either per rank (13 of them, from A to K), or per card (52
+/// of them, indexed like Card::from_index) for bets that care about suits. In
+/// config, it's written as e.g.
+/// `{ name: my_banker, bet: BankerWin, tags: [1, 1, 1, 2, -1, -2, -1, -1, 0, 0, 0, 0, 0] }`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "CountingSystemConfig", into = "CountingSystemConfig")]
+pub struct CountingSystem {
+    pub name: String,
+    /// The bet the count is designed for, if any.
+    pub bet: Option<HandsBet>,
+    tags: Vec<f64>, // Always 52 of them.
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CountingSystemConfig {
+    name: String,
+    #[serde(default)]
+    bet: Option<HandsBet>,
+    tags: Vec<f64>,
+}
+
+/// Tracks the running count of a shoe as cards are dealt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningCount<'a> {
+    system: &'a CountingSystem,
+    count: f64,
+}
+
+impl CountingSystem {
+    /// tags must have either 13 (per rank) or 52 (per card) entries.
+    pub fn new(name: &str, bet: Option<HandsBet>, tags: &[f64]) -> Result<Self, Error> {
+        let tags = match tags.len() {
+            13 => (0..52).map(|i| tags[i % 13]).collect(),
+            52 => tags.to_vec(),
+            len => return Err(Error::InvalidTagsLength(len)),
+        };
+        Ok(Self {
+            name: name.to_string(),
+            bet,
+            tags,
+        })
+    }
+
+    /// The published counts, keyed by their names. For now it's only Eliot
+    /// Jacobson's Dragon 7 count.
+    pub fn presets() -> Vec<CountingSystem> {
+        let dragon_7 = [0., 0., 0., -1., -1., -1., -1., 2., 2., 0., 0., 0., 0.];
+        vec![Self::new("dragon_7", Some(HandsBet::Dragon7), &dragon_7).unwrap()]
+    }
+
+    /// Counts of Banker, Player and Tie made by this crate rather than
+    /// published, i.e. the effects of removal of an 8-deck shoe, scaled and
+    /// rounded to small integers.
+    pub fn eor_counts() -> Vec<CountingSystem> {
+        let banker = [1., 1., 1., 2., -1., -2., -1., -1., 0., 0., 0., 0., 0.];
+        let player = banker.map(|tag| -tag);
+        let tie = [0., -1., -1., -1., -1., -5., -4., 3., 2., 2., 2., 2., 2.];
+        [
+            ("eor_banker", HandsBet::BankerWin, banker),
+            ("eor_player", HandsBet::PlayerWin, player),
+            ("eor_tie", HandsBet::Tie, tie),
+        ]
+        .into_iter()
+        .map(|(name, bet, tags)| Self::new(name, Some(bet), &tags).unwrap())
+        .collect()
+    }
+
+    /// Returns the preset or the EOR count of the name, or None if there's no
+    /// such count.
+    pub fn by_name(name: &str) -> Option<CountingSystem> {
+        Self::presets()
+            .into_iter()
+            .chain(Self::eor_counts())
+            .find(|system| system.name == name)
+    }
+
+    pub fn get_tag(&self, card: Card) -> f64 {
+        self.tags[card.to_index()]
+    }
+
+    /// Returns the running count of a shoe of number_of_decks decks, whose
+    /// remaining cards are given by the counter.
+    pub fn get_running_count(&self, counter: &Counter, number_of_decks: u32) -> f64 {
+        counter
+            .get_card_count()
+            .iter()
+            .zip(&self.tags)
+            .map(|(&count, tag)| tag * (number_of_decks as f64 - count as f64))
+            .sum()
+    }
+
+    /// Returns the running count per remaining deck.
+    pub fn get_true_count(&self, counter: &Counter, number_of_decks: u32) -> f64 {
+        to_true_count(self.get_running_count(counter, number_of_decks), counter)
+    }
+
+    /// Returns the correlation between the tags and the effects of removal of
+    /// the bet over all 52 cards, a.k.a. the betting correlation. The closer
+    /// it is to 1, the better the count follows the ex of the bet. Returns
+    /// None if the bet isn't offered or all the tags are the same.
+    pub fn get_betting_correlation(&self, eor: &EffectOfRemoval, bet: HandsBet) -> Option<f64> {
+        let eor = eor.get_by_card(bet)?;
+        let n = 52.0;
+        let (mean_x, mean_y) = (
+            self.tags.iter().sum::<f64>() / n,
+            eor.iter().sum::<f64>() / n,
+        );
+        let (mut sxx, mut syy, mut sxy) = (0.0, 0.0, 0.0);
+        for (x, y) in self.tags.iter().zip(&eor) {
+            sxx += (x - mean_x) * (x - mean_x);
+            syy += (y - mean_y) * (y - mean_y);
+            sxy += (x - mean_x) * (y - mean_y);
+        }
+        if sxx == 0.0 || syy == 0.0 {
+            return None;
+        }
+        Some(sxy / (sxx * syy).sqrt())
+    }
+
+    /// Returns the sum of the tags of a full shoe, which is 0 for a balanced
+    /// count.
+    pub fn get_imbalance(&self, number_of_decks: u32) -> f64 {
+        self.tags.iter().sum::<f64>() * number_of_decks as f64
+    }
+}
+
+impl<'a> RunningCount<'a> {
+    pub fn new(system: &'a CountingSystem) -> Self {
+        Self { system, count: 0.0 }
+    }
+
+    pub fn get_system(&self) -> &CountingSystem {
+        self.system
+    }
+
+    pub fn add_dealt_card(&mut self, card: Card) {
+        self.count += self.system.get_tag(card);
+    }
+
+    /// Undoes add_dealt_card().
+    pub fn remove_dealt_card(&mut self, card: Card) {
+        self.count -= self.system.get_tag(card);
+    }
+
+    /// Starts a new shoe.
+    pub fn reset(&mut self) {
+        self.count = 0.0;
+    }
+
+    pub fn get_running_count(&self) -> f64 {
+        self.count
+    }
+
+    /// Returns the running count per remaining deck, where the counter has
+    /// the remaining cards.
+    pub fn get_true_count(&self, counter: &Counter) -> f64 {
+        to_true_count(self.count, counter)
+    }
+}
+
+fn to_true_count(running_count: f64, counter: &Counter) -> f64 {
+    let decks = counter.get_total() as f64 / 52.0;
+    if decks == 0.0 {
+        return 0.0;
+    }
+    running_count / decks
+}
+
+impl TryFrom<CountingSystemConfig> for CountingSystem {
+    type Error = Error;
+
+    fn try_from(config: CountingSystemConfig) -> Result<Self, Self::Error> {
+        Self::new(&config.name, config.bet, &config.tags)
+    }
+}
+
+impl From<CountingSystem> for CountingSystemConfig {
+    fn from(system: CountingSystem) -> Self {
+        // Write the tags per rank if suits don't matter.
+        let per_rank = (13..52).all(|i| system.tags[i] == system.tags[i % 13]);
+        let tags = if per_rank {
+            system.tags[..13].to_vec()
+        } else {
+            system.tags
+        };
+        Self {
+            name: system.name,
+            bet: system.bet,
+            tags,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counting_system() {
+        for system in CountingSystem::presets()
+            .into_iter()
+            .chain(CountingSystem::eor_counts())
+        {
+            assert_eq!(system.get_imbalance(8), 0.0, "{}", system.name);
+        }
+        assert!(CountingSystem::new("bad", None, &[1.0; 10]).is_err());
+
+        let system = CountingSystem::by_name("eor_banker").unwrap();
+        let mut counter = Counter::new(8);
+        let mut running_count = RunningCount::new(&system);
+        for i in [3, 16, 29, 5, 44, 0] {
+            let card = Card::from_index(i);
+            counter.remove_card(card);
+            running_count.add_dealt_card(card);
+        }
+        // 4, 4, 4, 6, 6, A.
+        assert_eq!(running_count.get_running_count(), 3.0);
+        assert_eq!(system.get_running_count(&counter, 8), 3.0);
+        let true_count = 3.0 / ((8.0 * 52.0 - 6.0) / 52.0);
+        assert_eq!(running_count.get_true_count(&counter), true_count);
+        assert_eq!(system.get_true_count(&counter, 8), true_count);
+
+        running_count.remove_dealt_card(Card::from_index(0));
+        assert_eq!(running_count.get_running_count(), 2.0);
+
+        // The EOR counts are rounded from the effects of removal.
+        let rule = crate::calculation::tests::get_typical_rule();
+        let eor = crate::calculation::effect_of_removal::calculate(&rule).unwrap();
+        for (name, bet) in [
+            ("eor_banker", HandsBet::BankerWin),
+            ("eor_player", HandsBet::PlayerWin),
+            ("eor_tie", HandsBet::Tie),
+        ] {
+            let system = CountingSystem::by_name(name).unwrap();
+            let correlation = system.get_betting_correlation(&eor, bet).unwrap();
+            assert!(correlation > 0.9, "{}: {}", name, correlation);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+//! Dragon Tiger shares the shoe and the card model with Baccarat, but each
+//! side (Dragon and Tiger) only gets one card. The higher card wins, where
+//! A is the lowest and K is the highest.
+
+use serde::{Deserialize, Serialize};
+
+pub mod calculation;
+pub mod game;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DragonTigerRule {
+    pub number_of_decks: u32,
+    pub cut_card_proportion: f64,
+    pub discard_at_start: bool,
+
+    pub payouts: DragonTigerPayouts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DragonTigerPayouts {
+    pub dragon_tiger_win: f64,
+
+    /// When it's a tie and you bet on Dragon or Tiger, you lose half of your
+    /// bet if this is true. Otherwise you lose all.
+    pub lose_half_on_tie: bool,
+
+    pub tie: f64,
+    pub suited_tie: f64,
+
+    // Side bets on the card of either Dragon or Tiger. A 7 loses them all.
+    pub big_small: f64, // Big is 8 to K. Small is A to 6.
+    pub odd_even: f64,
+}
+
+impl Default for DragonTigerRule {
+    fn default() -> Self {
+        Self {
+            number_of_decks: 8,
+            cut_card_proportion: 0.85,
+            discard_at_start: true,
+
+            payouts: DragonTigerPayouts {
+                dragon_tiger_win: 1.0,
+                lose_half_on_tie: true,
+
+                tie: 8.0,
+                suited_tie: 50.0,
+
+                big_small: 1.0,
+                odd_even: 1.0,
+            },
+        }
+    }
+}
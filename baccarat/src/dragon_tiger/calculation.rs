@@ -0,0 +1,234 @@
+use super::{
+    game::{DragonTigerBet, DragonTigerSide},
+    DragonTigerRule,
+};
+use crate::{
+    calculation::{check_card_count, Counter},
+    Error,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone)]
+pub struct DragonTigerSolver<'a> {
+    rule: &'a DragonTigerRule,
+    solution: DragonTigerSolution,
+}
+
+impl<'a> DragonTigerSolver<'a> {
+    pub fn new(rule: &'a DragonTigerRule) -> Self {
+        Self {
+            rule,
+            solution: Default::default(),
+        }
+    }
+
+    /// Note that this is NOT thread-safe.
+    pub fn solve(&mut self, counter: &Counter) -> &DragonTigerSolution {
+        functional::calculate(self.rule, counter, &mut self.solution);
+        &self.solution
+    }
+
+    /// The same as solve(), but returns an error rather than a meaningless
+    /// solution if there aren't 2 cards left.
+    pub fn try_solve(&mut self, counter: &Counter) -> Result<&DragonTigerSolution, Error> {
+        functional::try_calculate(self.rule, counter, &mut self.solution)?;
+        Ok(&self.solution)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DragonTigerSolution {
+    pub p_dragon_win: f64,
+    pub ex_dragon_win: f64,
+
+    pub p_tiger_win: f64,
+    pub ex_tiger_win: f64,
+
+    pub p_tie: f64,
+    pub ex_tie: f64,
+
+    pub p_suited_tie: f64,
+    pub ex_suited_tie: f64,
+
+    pub sol_dragon: DragonTigerSolutionSide,
+    pub sol_tiger: DragonTigerSolutionSide,
+}
+
+/// Side bets on the card of either Dragon or Tiger.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DragonTigerSolutionSide {
+    pub p_big: f64,
+    pub ex_big: f64,
+
+    pub p_small: f64,
+    pub ex_small: f64,
+
+    pub p_odd: f64,
+    pub ex_odd: f64,
+
+    pub p_even: f64,
+    pub ex_even: f64,
+}
+
+impl DragonTigerSolution {
+    /// This function gets the best bet based on each bet's ex. We only care
+    /// those bets whose probabilities are greater than p_threshold.
+    pub fn get_best_bet(&self, p_threshold: f64) -> (DragonTigerBet, f64) {
+        let mut bets = vec![
+            (
+                DragonTigerBet::Dragon,
+                self.p_dragon_win,
+                self.ex_dragon_win,
+            ),
+            (DragonTigerBet::Tiger, self.p_tiger_win, self.ex_tiger_win),
+            (DragonTigerBet::Tie, self.p_tie, self.ex_tie),
+            (
+                DragonTigerBet::SuitedTie,
+                self.p_suited_tie,
+                self.ex_suited_tie,
+            ),
+        ];
+        for (side, s) in [
+            (DragonTigerSide::Dragon, &self.sol_dragon),
+            (DragonTigerSide::Tiger, &self.sol_tiger),
+        ] {
+            bets.push((DragonTigerBet::Big(side), s.p_big, s.ex_big));
+            bets.push((DragonTigerBet::Small(side), s.p_small, s.ex_small));
+            bets.push((DragonTigerBet::Odd(side), s.p_odd, s.ex_odd));
+            bets.push((DragonTigerBet::Even(side), s.p_even, s.ex_even));
+        }
+
+        let (mut max_bet, mut max_ex) = (DragonTigerBet::PlaceHolder, f64::MIN);
+        for (bet, p, ex) in bets {
+            if p > p_threshold && max_ex < ex {
+                (max_bet, max_ex) = (bet, ex);
+            }
+        }
+        (max_bet, max_ex)
+    }
+
+    fn calculate_ex_based_on_p(&mut self, rule: &DragonTigerRule) {
+        let payouts = &rule.payouts;
+        let lose_on_tie = if payouts.lose_half_on_tie { 0.5 } else { 1.0 };
+
+        self.ex_dragon_win = self.p_dragon_win * payouts.dragon_tiger_win
+            - self.p_tiger_win
+            - self.p_tie * lose_on_tie;
+        self.ex_tiger_win = self.p_tiger_win * payouts.dragon_tiger_win
+            - self.p_dragon_win
+            - self.p_tie * lose_on_tie;
+        self.ex_tie = self.p_tie * payouts.tie - (1.0 - self.p_tie);
+        self.ex_suited_tie = self.p_suited_tie * payouts.suited_tie - (1.0 - self.p_suited_tie);
+
+        for s in [&mut self.sol_dragon, &mut self.sol_tiger] {
+            s.ex_big = s.p_big * payouts.big_small - (1.0 - s.p_big);
+            s.ex_small = s.p_small * payouts.big_small - (1.0 - s.p_small);
+            s.ex_odd = s.p_odd * payouts.odd_even - (1.0 - s.p_odd);
+            s.ex_even = s.p_even * payouts.odd_even - (1.0 - s.p_even);
+        }
+    }
+}
+
+pub mod functional {
+    use super::*;
+
+    pub fn try_calculate(
+        // Input
+        rule: &DragonTigerRule,
+        counter: &Counter,
+
+        // Output
+        solution: &mut DragonTigerSolution,
+    ) -> Result<(), Error> {
+        check_card_count(counter.get_card_count())?;
+        if counter.get_total() < 2 {
+            return Err(Error::NotEnoughCards(counter.get_total()));
+        }
+        calculate(rule, counter, solution);
+        Ok(())
+    }
+
+    pub fn calculate(
+        // Input
+        rule: &DragonTigerRule,
+        counter: &Counter,
+
+        // Output
+        solution: &mut DragonTigerSolution,
+    ) {
+        *solution = Default::default();
+        let total = counter.get_total() as f64;
+        let total_pairs = total * (total - 1.0);
+
+        // Step 1: Calculate probabilities of bets on comparing the two cards.
+        let value_count = counter.get_value_count();
+        let mut lower_count = 0.0;
+        for &count in value_count {
+            let count = count as f64;
+            // Dragon gets this value, and Tiger gets a lower one.
+            solution.p_dragon_win += count * lower_count / total_pairs;
+            solution.p_tie += count * (count - 1.0).max(0.0) / total_pairs;
+            lower_count += count;
+        }
+        solution.p_tiger_win = solution.p_dragon_win;
+        for &count in counter.get_card_count() {
+            let count = count as f64;
+            solution.p_suited_tie += count * (count - 1.0).max(0.0) / total_pairs;
+        }
+
+        // Step 2: Calculate probabilities of side bets on a single card. Both
+        // Dragon and Tiger's card can be any card in the shoe with the same
+        // probability, no matter which one is dealt first.
+        let s = &mut solution.sol_dragon;
+        for (i, &count) in value_count.iter().enumerate() {
+            let p = count as f64 / total;
+            let value = i + 1;
+            match value {
+                1..=6 => s.p_small += p,
+                7 => continue,
+                _ => s.p_big += p,
+            }
+            if value % 2 == 1 {
+                s.p_odd += p;
+            } else {
+                s.p_even += p;
+            }
+        }
+        solution.sol_tiger = solution.sol_dragon.clone();
+
+        // Step 3: Calculate expectations.
+        solution.calculate_ex_based_on_p(rule);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calculation::tests::assert_float_equal;
+
+    #[test]
+    fn test_calculation_result() {
+        // Ground truth based on 8 decks.
+        const EX_DRAGON_WIN: f64 = -0.037349;
+        const EX_TIE: f64 = -0.327711;
+        const EX_SUITED_TIE: f64 = -0.139759;
+        const EX_BIG: f64 = -0.076923;
+
+        let rule = DragonTigerRule::default();
+        let counter = Counter::new(8);
+        let mut solution: DragonTigerSolution = Default::default();
+
+        functional::calculate(&rule, &counter, &mut solution);
+
+        assert_float_equal(
+            solution.p_dragon_win + solution.p_tiger_win + solution.p_tie,
+            1.0,
+        );
+        assert_float_equal(solution.ex_dragon_win, EX_DRAGON_WIN);
+        assert_float_equal(solution.ex_tiger_win, EX_DRAGON_WIN);
+        assert_float_equal(solution.ex_tie, EX_TIE);
+        assert_float_equal(solution.ex_suited_tie, EX_SUITED_TIE);
+        assert_float_equal(solution.sol_dragon.ex_big, EX_BIG);
+        assert_float_equal(solution.sol_tiger.ex_odd, EX_BIG);
+    }
+}
@@ -0,0 +1,219 @@
+use super::{calculation::DragonTigerSolution, DragonTigerRule};
+use crate::{calculation::Counter, card::Card, game::DealerProvider};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DragonTigerSide {
+    Dragon,
+    Tiger,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DragonTigerBet {
+    PlaceHolder,
+
+    Dragon,
+    Tiger,
+    Tie,
+    SuitedTie,
+
+    Big(DragonTigerSide),
+    Small(DragonTigerSide),
+    Odd(DragonTigerSide),
+    Even(DragonTigerSide),
+}
+
+#[derive(Debug, Clone)]
+pub struct DragonTigerRoundResult<'a> {
+    rule: &'a DragonTigerRule,
+    /// Indicates how much you earn from casino. This will be 0 when player neither
+    /// win nor lose, and will be negative when player lose.
+    pub total_profit: i64,
+    pub details: HashMap<DragonTigerBet, i64>,
+}
+
+impl<'a> DragonTigerRoundResult<'a> {
+    pub fn new(rule: &'a DragonTigerRule) -> Self {
+        Self {
+            rule,
+            total_profit: 0,
+            details: HashMap::new(),
+        }
+    }
+
+    pub fn calculate_with_cards_and_bet(
+        &mut self,
+        dragon: Card,
+        tiger: Card,
+        bets: &HashMap<DragonTigerBet, i64>,
+    ) {
+        self.total_profit = 0;
+        self.details.clear();
+        let payouts = &self.rule.payouts;
+
+        fn f(money: i64, payout: f64) -> i64 {
+            (money as f64 * payout) as i64
+        }
+        let win_or_lose = |money: i64, win: bool, payout: f64| {
+            if win {
+                f(money, payout)
+            } else {
+                -money
+            }
+        };
+        let card_of = |side: DragonTigerSide| match side {
+            DragonTigerSide::Dragon => dragon,
+            DragonTigerSide::Tiger => tiger,
+        };
+
+        for (bet, money) in bets {
+            let money = *money;
+            let profit = match *bet {
+                DragonTigerBet::Dragon | DragonTigerBet::Tiger => {
+                    let ordering = if *bet == DragonTigerBet::Dragon {
+                        dragon.value.cmp(&tiger.value)
+                    } else {
+                        tiger.value.cmp(&dragon.value)
+                    };
+                    match ordering {
+                        Ordering::Less => -money,
+                        Ordering::Equal if payouts.lose_half_on_tie => -money / 2,
+                        Ordering::Equal => -money,
+                        Ordering::Greater => f(money, payouts.dragon_tiger_win),
+                    }
+                }
+                DragonTigerBet::Tie => win_or_lose(money, dragon.value == tiger.value, payouts.tie),
+                DragonTigerBet::SuitedTie => {
+                    win_or_lose(money, dragon == tiger, payouts.suited_tie)
+                }
+
+                DragonTigerBet::Big(side) => {
+                    win_or_lose(money, card_of(side).value >= 8, payouts.big_small)
+                }
+                DragonTigerBet::Small(side) => {
+                    win_or_lose(money, card_of(side).value <= 6, payouts.big_small)
+                }
+                DragonTigerBet::Odd(side) => {
+                    let value = card_of(side).value;
+                    win_or_lose(money, value != 7 && value % 2 == 1, payouts.odd_even)
+                }
+                DragonTigerBet::Even(side) => {
+                    win_or_lose(money, card_of(side).value % 2 == 0, payouts.odd_even)
+                }
+
+                DragonTigerBet::PlaceHolder => panic!("You cannot bet on PlaceHolder!"),
+            };
+
+            self.details.insert(*bet, profit);
+            self.total_profit += profit;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DragonTigerGame<
+    'a,
+    D: DealerProvider,
+    G: DragonTigerGamblerProvider,
+    S: DragonTigerSolverProvider,
+> {
+    rule: &'a DragonTigerRule,
+    counter: Counter,
+    round_result: DragonTigerRoundResult<'a>,
+
+    dealer: D,
+    gambler: G,
+    solver: S,
+
+    should_start_new_shoe: bool,
+    cards_before_cut: u32,
+}
+
+impl<'a, T: DealerProvider, U: DragonTigerGamblerProvider, S: DragonTigerSolverProvider>
+    DragonTigerGame<'a, T, U, S>
+{
+    pub fn new(rule: &'a DragonTigerRule, dealer: T, gambler: U, solver: S) -> Self {
+        Self {
+            rule,
+            counter: Counter::new(rule.number_of_decks),
+            round_result: DragonTigerRoundResult::new(rule),
+
+            dealer,
+            gambler,
+            solver,
+
+            should_start_new_shoe: true,
+            cards_before_cut: 0,
+        }
+    }
+
+    pub fn start_game_loop(&mut self) {
+        loop {
+            self.play_round();
+        }
+    }
+
+    /// Plays a single round, starting a new shoe first if the cut card was
+    /// reached in the last round.
+    pub fn play_round(&mut self) {
+        if self.should_start_new_shoe {
+            self.gambler.on_new_shoe();
+
+            // Initialize.
+            self.should_start_new_shoe = false;
+            self.counter = Counter::new(self.rule.number_of_decks);
+            self.cards_before_cut = 0;
+
+            self.dealer.start_new_shoe();
+
+            // Discard some cards, the same as Baccarat.
+            if self.rule.discard_at_start {
+                let discarded_card = self.get_card_from_dealer();
+                let discarded_cards = match discarded_card.to_bcr_value_index() {
+                    0 => 10,
+                    v => v,
+                } as u32;
+                self.cards_before_cut += discarded_cards;
+                self.gambler.on_discard(discarded_card);
+                self.dealer.discard_cards(discarded_cards);
+            }
+        }
+
+        self.gambler.on_round_start();
+
+        let solution = self.solver.solve(&self.counter);
+        let bets = self.gambler.place_bet(solution) as *const _;
+
+        let dragon = self.get_card_from_dealer();
+        let tiger = self.get_card_from_dealer();
+
+        self.round_result
+            .calculate_with_cards_and_bet(dragon, tiger, unsafe { &*bets });
+        self.gambler.on_round_end(dragon, tiger, &self.round_result);
+    }
+
+    fn get_card_from_dealer(&mut self) -> Card {
+        let card = self.dealer.deal_card();
+        self.counter.remove_card(card);
+        self.cards_before_cut += 1;
+        if self.dealer.is_cut_card_reached() {
+            self.gambler.on_cut_card_reached(self.cards_before_cut);
+            self.should_start_new_shoe = true;
+        }
+        card
+    }
+}
+
+pub trait DragonTigerGamblerProvider {
+    fn place_bet(&mut self, solution: &DragonTigerSolution) -> &HashMap<DragonTigerBet, i64>;
+    fn on_new_shoe(&mut self);
+    fn on_discard(&mut self, card: Card);
+    fn on_round_start(&mut self);
+    fn on_round_end(&mut self, dragon: Card, tiger: Card, round_result: &DragonTigerRoundResult);
+    fn on_cut_card_reached(&mut self, cards_before_cut: u32);
+}
+
+pub trait DragonTigerSolverProvider {
+    fn solve(&mut self, counter: &Counter) -> &DragonTigerSolution;
+}
@@ -0,0 +1,57 @@
+use crate::card::Card;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Error {
+    /// The index of a card must be < 52.
+    InvalidCardIndex(usize),
+    /// The card count of a counter must have exactly 52 entries.
+    InvalidCardCountLength(usize),
+    /// The card appears more times than the shoe contains.
+    TooManyCards(Card),
+    /// The count of the card is more than MAX_DECKS decks can hold.
+    CardCountTooLarge(Card, u32),
+    /// The card to remove has run out.
+    CardNotFound(Card),
+    /// There are only these many cards left, which are not enough to start
+    /// a round.
+    NotEnoughCards(u32),
+    /// The feature isn't supported for the rule.
+    Unsupported(&'static str),
+    /// The tags of a counting system must have 13 (per rank) or 52 (per
+    /// card) entries.
+    InvalidTagsLength(usize),
+    /// The known cards of the hands can't be dealt in a round, e.g. a hand
+    /// has a third card while it should stand.
+    InconsistentHands,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidCardIndex(index) => write!(f, "Index must be < 52, got {}", index),
+            Error::InvalidCardCountLength(len) => {
+                write!(f, "Array length must be 52, got {}", len)
+            }
+            Error::TooManyCards(card) => write!(f, "Too many {:?} in the shoe", card),
+            Error::CardCountTooLarge(card, count) => write!(
+                f,
+                "{} {:?} are more than {} decks can hold",
+                count,
+                card,
+                crate::calculation::MAX_DECKS
+            ),
+            Error::CardNotFound(card) => write!(f, "No {:?} left in the shoe", card),
+            Error::NotEnoughCards(total) => {
+                write!(f, "Only {} cards left, which are not enough", total)
+            }
+            Error::Unsupported(feature) => write!(f, "Not supported: {}", feature),
+            Error::InvalidTagsLength(len) => {
+                write!(f, "Tags must have 13 or 52 entries, got {}", len)
+            }
+            Error::InconsistentHands => write!(f, "The known cards can't be dealt in a round"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
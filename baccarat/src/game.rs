@@ -1,16 +1,22 @@
 mod hands;
 
-pub use hands::{Hand, HandsBet, HandsResult, HandsResultBonus, RoundResult};
+pub use hands::{Hand, HandsBet, HandsResult, HandsResultBonus, RoundOutcome, RoundResult};
 use std::collections::HashMap;
 
 use crate::{
-    calculation::{Counter, Solution, RULE_CHART},
+    calculation::{Counter, Solution, CHEMIN_DE_FER_BANKER_OPTIONS, CHEMIN_DE_FER_PLAYER_OPTION},
     card::Card,
     Rule,
 };
 
 #[derive(Debug, Clone)]
-pub struct Game<'a, D: DealerProvider, G: GamblerProvider, S: SolverProvider> {
+pub struct Game<
+    'a,
+    D: DealerProvider,
+    G: GamblerProvider,
+    S: SolverProvider,
+    C: DecisionProvider = PuntoBancoDecision,
+> {
     rule: &'a Rule,
     counter: Counter,
     player: Hand,
@@ -20,6 +26,7 @@ pub struct Game<'a, D: DealerProvider, G: GamblerProvider, S: SolverProvider> {
     dealer: D,
     gambler: G,
     solver: S,
+    decider: C,
 
     discarded_card: Card,
     should_start_new_shoe: bool,
@@ -28,6 +35,21 @@ pub struct Game<'a, D: DealerProvider, G: GamblerProvider, S: SolverProvider> {
 
 impl<'a, T: DealerProvider, U: GamblerProvider, S: SolverProvider> Game<'a, T, U, S> {
     pub fn new(rule: &'a Rule, dealer: T, gambler: U, solver: S) -> Self {
+        Self::with_decision_provider(rule, dealer, gambler, solver, PuntoBancoDecision)
+    }
+}
+
+impl<'a, T: DealerProvider, U: GamblerProvider, S: SolverProvider, C: DecisionProvider>
+    Game<'a, T, U, S, C>
+{
+    /// The decider is only consulted in Chemin de fer.
+    pub fn with_decision_provider(
+        rule: &'a Rule,
+        dealer: T,
+        gambler: U,
+        solver: S,
+        decider: C,
+    ) -> Self {
         let number_of_decks = rule.number_of_decks;
         Self {
             rule,
@@ -39,6 +61,7 @@ impl<'a, T: DealerProvider, U: GamblerProvider, S: SolverProvider> Game<'a, T, U
             dealer,
             gambler,
             solver,
+            decider,
 
             discarded_card: Default::default(),
             should_start_new_shoe: true,
@@ -48,64 +71,88 @@ impl<'a, T: DealerProvider, U: GamblerProvider, S: SolverProvider> Game<'a, T, U
 
     pub fn start_game_loop(&mut self) {
         loop {
-            if self.should_start_new_shoe {
-                self.gambler.on_new_shoe();
-
-                // Initialize.
-                self.should_start_new_shoe = false;
-                self.counter = Counter::new(self.rule.number_of_decks);
-                self.cards_before_cut = 0;
-
-                self.dealer.start_new_shoe();
-
-                // Discard some cards.
-                if self.rule.discard_at_start {
-                    self.discarded_card = self.get_card_from_dealer();
-                    let discarded_cards = self.discarded_card.to_bcr_value_index();
-                    let discarded_cards = if discarded_cards == 0 {
-                        10
-                    } else {
-                        discarded_cards
-                    } as u32;
-                    self.cards_before_cut += discarded_cards;
-                    self.gambler.on_discard(self.discarded_card);
-                    self.dealer.discard_cards(discarded_cards);
-                }
+            self.play_round();
+        }
+    }
+
+    /// Plays a single round, starting a new shoe first if the cut card was
+    /// reached in the last round.
+    pub fn play_round(&mut self) {
+        if self.should_start_new_shoe {
+            self.gambler.on_new_shoe();
+
+            // Initialize.
+            self.should_start_new_shoe = false;
+            self.counter = Counter::new(self.rule.number_of_decks);
+            self.cards_before_cut = 0;
+
+            self.dealer.start_new_shoe();
+
+            // Discard some cards.
+            if self.rule.discard_at_start {
+                self.discarded_card = self.get_card_from_dealer();
+                let discarded_cards = self.discarded_card.to_bcr_value_index();
+                let discarded_cards = if discarded_cards == 0 {
+                    10
+                } else {
+                    discarded_cards
+                } as u32;
+                self.cards_before_cut += discarded_cards;
+                self.gambler.on_discard(self.discarded_card);
+                self.dealer.discard_cards(discarded_cards);
             }
+        }
 
-            self.gambler.on_round_start();
-
-            let solution = self.solver.solve(&self.counter);
-            let bets = self.gambler.place_bet(solution) as *const _;
-
-            self.player.third = None;
-            self.banker.third = None;
-            self.player.initial[0] = self.get_card_from_dealer();
-            self.banker.initial[0] = self.get_card_from_dealer();
-            self.player.initial[1] = self.get_card_from_dealer();
-            self.banker.initial[1] = self.get_card_from_dealer();
-            if !self.player.is_natural() && !self.banker.is_natural() {
-                // Check if player should draw the extra card.
-                if self.player.get_sum() <= 5 {
-                    self.player.third = Some(self.get_card_from_dealer());
-                }
+        self.gambler.on_round_start();
+
+        let solution = self.solver.solve(&self.counter);
+        let bets = self.gambler.place_bet(solution) as *const _;
+
+        self.player.third = None;
+        self.banker.third = None;
+        self.player.initial[0] = self.get_card_from_dealer();
+        self.banker.initial[0] = self.get_card_from_dealer();
+        self.player.initial[1] = self.get_card_from_dealer();
+        self.banker.initial[1] = self.get_card_from_dealer();
+        if !self.player.is_natural() && !self.banker.is_natural() {
+            // Check if player should draw the extra card.
+            let player_sum = self.player.get_sum();
+            let player_draws =
+                if self.rule.chemin_de_fer && player_sum == CHEMIN_DE_FER_PLAYER_OPTION {
+                    self.decider.should_player_draw(&self.player)
+                } else {
+                    self.rule.tableau.should_player_draw(player_sum)
+                };
+            if player_draws {
+                self.player.third = Some(self.get_card_from_dealer());
+            }
 
-                // Check if banker should draw the extra card.
-                if let Some(player_third) = self.player.third {
-                    if RULE_CHART[self.banker.get_sum() as usize][player_third.to_bcr_value_index()]
-                    {
-                        self.banker.third = Some(self.get_card_from_dealer());
-                    }
-                } else if self.banker.get_sum() <= 5 {
+            // Check if banker should draw the extra card.
+            if let Some(player_third) = self.player.third {
+                let banker_sum = self.banker.get_sum();
+                let cell = (banker_sum, player_third.to_bcr_value_index() as u8);
+                let banker_draws =
+                    if self.rule.chemin_de_fer && CHEMIN_DE_FER_BANKER_OPTIONS.contains(&cell) {
+                        self.decider.should_banker_draw(&self.banker, player_third)
+                    } else {
+                        self.rule.tableau.should_banker_draw(cell.0, Some(cell.1))
+                    };
+                if banker_draws {
                     self.banker.third = Some(self.get_card_from_dealer());
                 }
+            } else if self
+                .rule
+                .tableau
+                .should_banker_draw(self.banker.get_sum(), None)
+            {
+                self.banker.third = Some(self.get_card_from_dealer());
             }
-
-            self.round_result
-                .calculate_with_hands_and_bet(&self.player, &self.banker, unsafe { &*bets });
-            self.gambler
-                .on_round_end(&self.player, &self.banker, &self.round_result);
         }
+
+        self.round_result
+            .calculate_with_hands_and_bet(&self.player, &self.banker, unsafe { &*bets });
+        self.gambler
+            .on_round_end(&self.player, &self.banker, &self.round_result);
     }
 
     fn get_card_from_dealer(&mut self) -> Card {
@@ -139,3 +186,25 @@ pub trait GamblerProvider {
 pub trait SolverProvider {
     fn solve(&mut self, counter: &Counter) -> &Solution;
 }
+
+/// Makes the decisions on the options of player and banker in Chemin de fer.
+pub trait DecisionProvider {
+    /// Called when player's initial total is CHEMIN_DE_FER_PLAYER_OPTION.
+    fn should_player_draw(&mut self, player: &Hand) -> bool;
+    /// Called when banker's initial total and player's third card are one of
+    /// CHEMIN_DE_FER_BANKER_OPTIONS.
+    fn should_banker_draw(&mut self, banker: &Hand, player_third: Card) -> bool;
+}
+
+/// Always draws on the options, just as the standard tableau of Punto Banco.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PuntoBancoDecision;
+
+impl DecisionProvider for PuntoBancoDecision {
+    fn should_player_draw(&mut self, _player: &Hand) -> bool {
+        true
+    }
+    fn should_banker_draw(&mut self, _banker: &Hand, _player_third: Card) -> bool {
+        true
+    }
+}
@@ -1,4 +1,5 @@
-use crate::{card::Card, Rule};
+use crate::{card::Card, side_bet::Winner, Rule};
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::HashMap;
 
@@ -31,6 +32,14 @@ impl Hand {
     pub fn is_initial_suit_pair(&self) -> bool {
         self.initial[0] == self.initial[1]
     }
+
+    pub fn get_number_of_cards(&self) -> u8 {
+        if self.third.is_some() {
+            3
+        } else {
+            2
+        }
+    }
 }
 
 impl std::fmt::Debug for Hand {
@@ -47,7 +56,44 @@ impl std::fmt::Debug for Hand {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The final totals and numbers of cards of both hands. This is all we need
+/// to settle any bet that doesn't care about ranks or suits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundOutcome {
+    pub player_total: u8,
+    pub banker_total: u8,
+    pub player_cards: u8,
+    pub banker_cards: u8,
+}
+
+impl RoundOutcome {
+    pub fn from_hands(player: &Hand, banker: &Hand) -> Self {
+        Self {
+            player_total: player.get_sum(),
+            banker_total: banker.get_sum(),
+            player_cards: player.get_number_of_cards(),
+            banker_cards: banker.get_number_of_cards(),
+        }
+    }
+
+    pub fn winner(&self) -> Winner {
+        match self.player_total.cmp(&self.banker_total) {
+            Ordering::Less => Winner::Banker,
+            Ordering::Equal => Winner::Tie,
+            Ordering::Greater => Winner::Player,
+        }
+    }
+
+    pub fn is_player_natural(&self) -> bool {
+        self.player_cards == 2 && self.player_total >= 8
+    }
+
+    pub fn is_banker_natural(&self) -> bool {
+        self.banker_cards == 2 && self.banker_total >= 8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum HandsBet {
     PlaceHolder,
 
@@ -62,10 +108,20 @@ pub enum HandsBet {
 
     PlayerBonus,
     BankerBonus,
+
+    TieOn(u8), // Param is the total, in range [0, 9].
+    Dragon7,
+    Panda8,
+    Super6,
+    Big,
+    Small,
+
+    SideBet(usize), // Param is the index in Rule::side_bets.
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
 pub enum HandsResult {
+    #[default]
     Lose,
 
     PlayerWin,
@@ -79,12 +135,17 @@ pub enum HandsResult {
 
     PlayerBonus(HandsResultBonus),
     BankerBonus(HandsResultBonus),
-}
 
-impl Default for HandsResult {
-    fn default() -> Self {
-        Self::Lose
-    }
+    TieOn(u8), // Param is the total, in range [0, 9].
+    Dragon7,
+    Panda8,
+    Super6(u8), // Param can be 2 or 3, i.e., the number of banker's cards.
+    Big,
+    Small,
+
+    SideBet(usize, usize), // Params are the indices of the side bet and its outcome.
+
+    NotOffered, // The bet isn't offered under the rule, so the money is returned.
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -127,12 +188,20 @@ impl<'a> RoundResult<'a> {
 
         let player_sum = player.get_sum();
         let banker_sum = banker.get_sum();
+        let is_dragon_7 = player_sum < banker_sum && banker_sum == 7 && banker.third.is_some();
+        let is_panda_8 = player_sum > banker_sum && player_sum == 8 && player.third.is_some();
+        let total_cards = player.get_number_of_cards() + banker.get_number_of_cards();
 
         fn f(money: i64, payout: f64) -> i64 {
             (money as f64 * payout) as i64
         }
+        const NOT_OFFERED: RoundBetResult = RoundBetResult(HandsResult::NotOffered, 0);
 
         for (hands_bet, money) in bets {
+            if !self.rule.is_offered(*hands_bet) {
+                self.details.insert(*hands_bet, NOT_OFFERED);
+                continue;
+            }
             let bet_result = match *hands_bet {
                 // Main bet 1: Player win
                 HandsBet::PlayerWin => match player_sum.cmp(&banker_sum) {
@@ -152,9 +221,14 @@ impl<'a> RoundResult<'a> {
                 }
                 // Main bet 3: Banker win
                 HandsBet::BankerWin => match player_sum.cmp(&banker_sum) {
-                    Ordering::Less => {
-                        RoundBetResult(HandsResult::BankerWin, f(*money, payouts.banker_win))
-                    }
+                    Ordering::Less => RoundBetResult(
+                        HandsResult::BankerWin,
+                        f(
+                            *money,
+                            self.rule
+                                .get_banker_win_payout(banker_sum, banker.get_number_of_cards()),
+                        ),
+                    ),
                     Ordering::Equal => RoundBetResult(HandsResult::Tie, 0),
                     Ordering::Greater => RoundBetResult(HandsResult::Lose, -money),
                 },
@@ -257,6 +331,77 @@ impl<'a> RoundResult<'a> {
                     }
                 }
 
+                // Side bet 7: Tie on total
+                HandsBet::TieOn(total) => {
+                    let payout = payouts.tie_on_total.expect("Tie on total is not offered!");
+                    if player_sum == banker_sum && player_sum == total {
+                        RoundBetResult(HandsResult::TieOn(total), f(*money, payout[total as usize]))
+                    } else {
+                        RoundBetResult(HandsResult::Lose, -money)
+                    }
+                }
+                // Side bet 8: Dragon 7
+                HandsBet::Dragon7 => {
+                    if is_dragon_7 {
+                        RoundBetResult(HandsResult::Dragon7, f(*money, payouts.dragon_7))
+                    } else {
+                        RoundBetResult(HandsResult::Lose, -money)
+                    }
+                }
+                // Side bet 9: Panda 8
+                HandsBet::Panda8 => {
+                    if is_panda_8 {
+                        RoundBetResult(HandsResult::Panda8, f(*money, payouts.panda_8))
+                    } else {
+                        RoundBetResult(HandsResult::Lose, -money)
+                    }
+                }
+
+                // Side bet 10: Super 6
+                HandsBet::Super6 => {
+                    let payout = payouts.super_6.expect("Super 6 is not offered!");
+                    if player_sum < banker_sum && banker_sum == 6 {
+                        let cards = banker.get_number_of_cards();
+                        RoundBetResult(
+                            HandsResult::Super6(cards),
+                            f(*money, payout[cards as usize - 2]),
+                        )
+                    } else {
+                        RoundBetResult(HandsResult::Lose, -money)
+                    }
+                }
+
+                // Side bet 11: Big
+                HandsBet::Big => {
+                    let payout = payouts.big_small.expect("Big/Small is not offered!");
+                    if total_cards >= 5 {
+                        RoundBetResult(HandsResult::Big, f(*money, payout[0]))
+                    } else {
+                        RoundBetResult(HandsResult::Lose, -money)
+                    }
+                }
+                // Side bet 12: Small
+                HandsBet::Small => {
+                    let payout = payouts.big_small.expect("Big/Small is not offered!");
+                    if total_cards == 4 {
+                        RoundBetResult(HandsResult::Small, f(*money, payout[1]))
+                    } else {
+                        RoundBetResult(HandsResult::Lose, -money)
+                    }
+                }
+
+                // Side bets declared in config
+                HandsBet::SideBet(index) => {
+                    let side_bet = &self.rule.side_bets[index];
+                    match side_bet.settle(&RoundOutcome::from_hands(player, banker)) {
+                        Some(i) => RoundBetResult(
+                            HandsResult::SideBet(index, i),
+                            f(*money, side_bet.outcomes[i].payout),
+                        ),
+                        None => RoundBetResult(HandsResult::Lose, -money),
+                    }
+                }
+
                 HandsBet::PlaceHolder => panic!("You cannot bet on PlaceHolder!"),
             };
 
@@ -1,8 +1,18 @@
+pub use error::Error;
+use game::HandsBet;
 use serde::{Deserialize, Serialize};
+use side_bet::SideBet;
+use tableau::Tableau;
 
+pub mod bet_sizing;
 pub mod calculation;
 pub mod card;
+pub mod counting;
+pub mod dragon_tiger;
+mod error;
 pub mod game;
+pub mod side_bet;
+pub mod tableau;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
@@ -10,7 +20,26 @@ pub struct Rule {
     pub cut_card_proportion: f64,
     pub discard_at_start: bool,
 
+    /// In EZ Baccarat, no commission is charged on banker win, but a banker
+    /// win on a three-card 7 is a push. Dragon 7 and Panda 8 are offered.
+    #[serde(default)]
+    pub ez_baccarat: bool,
+
+    /// In Chemin de fer, player may either draw or stand on 5, and banker
+    /// may either draw or stand in a few cells of the drawing chart. See
+    /// `calculation::CHEMIN_DE_FER_BANKER_OPTIONS`.
+    #[serde(default)]
+    pub chemin_de_fer: bool,
+
+    /// When to draw the third card. The standard Punto Banco tableau is used
+    /// if it's not given.
+    #[serde(default)]
+    pub tableau: Tableau,
+
     pub payouts: Payouts,
+
+    #[serde(default)]
+    pub side_bets: Vec<SideBet>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +48,11 @@ pub struct Payouts {
     pub banker_win: f64,
     pub tie: f64,
 
+    /// Banker win pays differently when banker wins with these totals. The
+    /// first matching one is used. Otherwise `banker_win` is used.
+    #[serde(default)]
+    pub banker_win_on_total: Vec<BankerWinOnTotal>,
+
     pub unsuit_pair: f64,
     pub either_pair: f64,
     pub perfect_pair: [f64; 2],
@@ -26,4 +60,67 @@ pub struct Payouts {
     pub bonus_unnatural: [f64; 6], // bonus[0] stands for "win by 4".
     pub bonus_natural_win: f64,
     pub bonus_natural_tie: f64,
+
+    #[serde(default = "default_dragon_7")]
+    pub dragon_7: f64,
+    #[serde(default = "default_panda_8")]
+    pub panda_8: f64,
+
+    /// Tie on each total (a.k.a. Egalité). None if it's not offered.
+    #[serde(default)]
+    pub tie_on_total: Option<[f64; 10]>, // tie_on_total[i] stands for "tie on i".
+
+    /// Big (5 or 6 cards are dealt) and Small (4 cards are dealt). None if
+    /// they're not offered.
+    #[serde(default)]
+    pub big_small: Option<[f64; 2]>, // big_small[0] is for "Big" and big_small[1] for "Small".
+
+    /// Super 6 (a.k.a. Lucky 6). None if it's not offered.
+    #[serde(default)]
+    pub super_6: Option<[f64; 2]>, // super_6[0] stands for "win by two-card 6".
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BankerWinOnTotal {
+    pub total: u8,
+    #[serde(default)]
+    pub cards: Option<u8>, // None means either two or three cards.
+    pub payout: f64,
+}
+
+impl Rule {
+    /// Returns the payout of "Banker Win" when banker wins with the given
+    /// final total and number of cards.
+    pub fn get_banker_win_payout(&self, total: u8, cards: u8) -> f64 {
+        if self.ez_baccarat && total == 7 && cards == 3 {
+            return 0.0;
+        }
+        self.payouts
+            .banker_win_on_total
+            .iter()
+            .find(|b| b.total == total && b.cards.is_none_or(|c| c == cards))
+            .map_or(self.payouts.banker_win, |b| b.payout)
+    }
+
+    /// Whether the bet is offered under the rule. Bets that aren't offered are
+    /// returned rather than settled.
+    pub fn is_offered(&self, bet: HandsBet) -> bool {
+        let payouts = &self.payouts;
+        match bet {
+            HandsBet::TieOn(total) => payouts.tie_on_total.is_some() && total <= 9,
+            HandsBet::Dragon7 | HandsBet::Panda8 => self.ez_baccarat,
+            HandsBet::Super6 => payouts.super_6.is_some(),
+            HandsBet::Big | HandsBet::Small => payouts.big_small.is_some(),
+            HandsBet::SideBet(index) => index < self.side_bets.len(),
+            _ => true,
+        }
+    }
+}
+
+fn default_dragon_7() -> f64 {
+    40.0
+}
+
+fn default_panda_8() -> f64 {
+    25.0
 }
@@ -0,0 +1,82 @@
+use crate::game::RoundOutcome;
+use serde::{Deserialize, Serialize};
+
+/// A side bet declared in the config rather than hard-coded in the crate.
+///
+/// The bet is decided by the final totals and the number of cards of both
+/// hands only, so it cannot depend on ranks or suits (e.g. pair bets).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SideBet {
+    pub name: String,
+
+    /// Outcomes are checked in order and the first matching one decides the
+    /// payout. When none of them matches, you lose the bet.
+    pub outcomes: Vec<SideBetOutcome>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SideBetOutcome {
+    #[serde(rename = "when")]
+    pub condition: SideBetCondition,
+    pub payout: f64,
+}
+
+/// All the given fields must hold for the condition to match. A field that
+/// is not given matches anything.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SideBetCondition {
+    pub winner: Option<Winner>,
+    pub player_total: Option<u8>,
+    pub banker_total: Option<u8>,
+    pub player_cards: Option<u8>,
+    pub banker_cards: Option<u8>,
+    pub total_cards: Option<u8>,
+    pub player_natural: Option<bool>,
+    pub banker_natural: Option<bool>,
+
+    /// The difference between the totals of the two hands.
+    pub margin: Option<u8>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Winner {
+    Player,
+    Banker,
+    Tie,
+}
+
+impl SideBet {
+    /// Returns the index of the outcome that decides this bet, or None if
+    /// the bet loses.
+    pub fn settle(&self, outcome: &RoundOutcome) -> Option<usize> {
+        self.outcomes
+            .iter()
+            .position(|o| o.condition.matches(outcome))
+    }
+}
+
+impl SideBetCondition {
+    pub fn matches(&self, outcome: &RoundOutcome) -> bool {
+        fn check<T: PartialEq>(expected: Option<T>, actual: T) -> bool {
+            expected.is_none_or(|e| e == actual)
+        }
+
+        check(self.winner, outcome.winner())
+            && check(self.player_total, outcome.player_total)
+            && check(self.banker_total, outcome.banker_total)
+            && check(self.player_cards, outcome.player_cards)
+            && check(self.banker_cards, outcome.banker_cards)
+            && check(
+                self.total_cards,
+                outcome.player_cards + outcome.banker_cards,
+            )
+            && check(self.player_natural, outcome.is_player_natural())
+            && check(self.banker_natural, outcome.is_banker_natural())
+            && check(
+                self.margin,
+                outcome.player_total.abs_diff(outcome.banker_total),
+            )
+    }
+}
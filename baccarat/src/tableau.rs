@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// The drawing rules of the third card. The standard Punto Banco tableau is
+/// used by default.
+///
+/// Totals 8 and 9 are naturals and never consulted, but they're kept so that
+/// every row is indexed from 0 to 9.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Tableau {
+    /// Indexed by player's initial total.
+    pub player: DrawingRow,
+    /// Indexed by banker's initial total, when player stands.
+    pub banker_when_player_stands: DrawingRow,
+    /// banker[i] is indexed by player's third card, when banker's initial
+    /// total is i and player draws.
+    pub banker: [DrawingRow; 10],
+}
+
+/// Whether to draw in each of the 10 cases. In config, it's written as a
+/// string of 10 'D' (draw) or 'S' (stand), e.g. "DDDDDDSSSS".
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(try_from = "String", into = "String")]
+pub struct DrawingRow(pub [bool; 10]);
+
+impl Tableau {
+    pub fn should_player_draw(&self, player_total: u8) -> bool {
+        self.player.0[player_total as usize]
+    }
+
+    /// player_third is the baccarat value of player's third card, or None
+    /// if player stands.
+    pub fn should_banker_draw(&self, banker_total: u8, player_third: Option<u8>) -> bool {
+        match player_third {
+            Some(card) => self.banker[banker_total as usize].0[card as usize],
+            None => self.banker_when_player_stands.0[banker_total as usize],
+        }
+    }
+}
+
+impl Default for Tableau {
+    fn default() -> Self {
+        let row = |s: &str| DrawingRow::try_from(s.to_string()).unwrap();
+        Self {
+            player: row("DDDDDDSSSS"),
+            banker_when_player_stands: row("DDDDDDSSSS"),
+            banker: [
+                row("DDDDDDDDDD"),
+                row("DDDDDDDDDD"),
+                row("DDDDDDDDDD"),
+                row("DDDDDDDDSD"),
+                row("SSDDDDDDSS"),
+                row("SSSSDDDDSS"),
+                row("SSSSSSDDSS"),
+                row("SSSSSSSSSS"),
+                row("SSSSSSSSSS"),
+                row("SSSSSSSSSS"),
+            ],
+        }
+    }
+}
+
+impl TryFrom<String> for DrawingRow {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let chars: Vec<char> = s.chars().collect();
+        if chars.len() != 10 {
+            return Err(format!("expected 10 'D' or 'S', got \"{}\"", s));
+        }
+        let mut row = [false; 10];
+        for (draw, c) in row.iter_mut().zip(chars) {
+            *draw = match c {
+                'D' => true,
+                'S' => false,
+                _ => return Err(format!("expected 'D' or 'S', got '{}' in \"{}\"", c, s)),
+            };
+        }
+        Ok(Self(row))
+    }
+}
+
+impl From<DrawingRow> for String {
+    fn from(row: DrawingRow) -> Self {
+        row.0
+            .iter()
+            .map(|&draw| if draw { 'D' } else { 'S' })
+            .collect()
+    }
+}
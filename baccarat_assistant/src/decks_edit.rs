@@ -5,13 +5,17 @@ use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct PropsDecksEdit {
-    pub on_confirm: Callback<u32>,
+    /// Called with the number of decks and the penetration.
+    pub on_confirm: Callback<(u32, f64)>,
     pub initial_num: u32,
+    /// The proportion of the shoe dealt before the cut card.
+    pub initial_penetration: f64,
 }
 
 #[function_component]
 pub fn DecksEdit(props: &PropsDecksEdit) -> Html {
     let new_decks = use_state(|| props.initial_num);
+    let new_penetration = use_state(|| props.initial_penetration);
 
     let onchange_input = {
         let new_decks = new_decks.clone();
@@ -30,11 +34,29 @@ pub fn DecksEdit(props: &PropsDecksEdit) -> Html {
         }
     };
 
+    let onchange_penetration = {
+        let new_penetration = new_penetration.clone();
+        move |e: Event| {
+            let target: Option<EventTarget> = e.target();
+            let input = target.and_then(|t| t.dyn_into::<HtmlInputElement>().ok());
+
+            if let Some(input) = input {
+                let value = input.value();
+                if let Ok(value) = value.parse::<f64>() {
+                    new_penetration.set(value / 100.0);
+                } else {
+                    error!("Cannot parse f64: ", value);
+                }
+            }
+        }
+    };
+
     let onclick_confirm = {
         let new_decks = new_decks.clone();
+        let new_penetration = new_penetration.clone();
         let on_confirm = props.on_confirm.clone();
         move |_| {
-            on_confirm.emit(*new_decks);
+            on_confirm.emit((*new_decks, *new_penetration));
         }
     };
 
@@ -45,6 +67,10 @@ pub fn DecksEdit(props: &PropsDecksEdit) -> Html {
         {"Change number of decks: "}
         <input type="number" value={(*new_decks).to_string()} onchange={Callback::from(onchange_input)} />
         </div>
+        <div id="change_penetration">
+        {"Cut card at (% of shoe): "}
+        <input type="number" value={(*new_penetration * 100.0).to_string()} onchange={Callback::from(onchange_penetration)} />
+        </div>
         <button id="confirm_change_decks" type="button" onclick={Callback::from(onclick_confirm)}>{"Confirm"}</button>
         </div>
         </div>
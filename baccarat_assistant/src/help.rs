@@ -27,9 +27,10 @@ pub fn HelpDiv(props: &PropsHelp) -> Html {
         <p>{"There are 4 control buttons. They are '↶' (Undo), '↷' (Redo), '↻' (Reset) and '?' (Help) respectively. Undo will cancel the most recent card dealing. Redo cancels undo. You can undo/redo for at most 99 times. Reset will literally reset the shoe to initial state, with the same number of cards for each card."}</p>
         <h2>{"Tables of insights"}</h2>
         <p>{"There are 3 tables here. The first table gives suggesstion on what you should bet (or more probably no bet at all). The second and third table are actually two horizontally separated parts of a big table, showing each bet's probability and expectation."}</p>
-        <p>{"If all you want is to maximize your profit, you should only focus on the first row of the first table, which gives you the best bet (perhaps no bet) based on expectation. "}<strong>{"Note that because you can hardly get a positive expectation from the main bets in Baccarat, you will hardly see it suggesting you to only bet on main bet. It always suggests you to bet on main bet along with side bet."}</strong>{" Here when calculating expectation, we assume that side bet can be at most half of main bet."}</p>
+        <p>{"If all you want is to grow your money, you should only focus on the first row of the first table, which gives you the best bets (perhaps no bet) and how much of your bankroll to put on each of them. The stakes of all bets are optimized together, since e.g. Banker Win and Banker Bonus win or lose together. They maximize the long-term growth of your bankroll (the Kelly criterion), so bet less (e.g. half of them) if you can't stand big swings. "}<strong>{"Note that because you can hardly get a positive expectation from the main bets in Baccarat, you will hardly see it suggesting you to only bet on main bet. A main bet is only suggested when it pays off on its own or offsets the risk of side bets."}</strong></p>
         <p>{"If you feel lucky or don't want to follow math, the first table also give you some suggestions. It gives you suggestions on main bets and side bets, based on both probability and expectation. The 3 columns called '1st', '2nd', '3rd' give you the best 3 suggestions. For example, let's say you just want to bet on some side bets today, and you just want to win side bets and don't care about main bet. In this case, you can focus on the 'Side bet (P)' row. The 'P' indicates the 3 best suggestions are ordered by probability, instead of expectation. Since total side bet cannot exeed half of main bet, you have to bet double on main bet in order to bet on side bet. When betting on main bet, you can refer to the 'Main bet (EX)' row, which gives you the best expectations among 3 main bets (plus no bet)."}</p>
         <p>{"The other 2 tables together give the probability and expectation for each bet. Most time you won't use them, but out of curiosity, you can observe how probabilities and expectations change with more and more cards dealt. And finally you can conclude that it's really really really hard to make money by playing Baccarat, a game designed to make you think you can make money :)"}</p>
+        <p>{"The 'Next round' row shows how likely the next round deals 4, 5 or 6 cards, and estimates how many rounds are left before the cut card, which helps to decide whether a good shoe is worth staying for. Press and hold '↻' (Reset) to set where the cut card is, as a percentage of the shoe. Cards you didn't enter (e.g. burned cards) are counted as remaining, so the estimate is a bit optimistic."}</p>
         </div>
         </div>
     }
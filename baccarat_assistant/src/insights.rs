@@ -1,14 +1,30 @@
-use baccarat::calculation::Solution;
+use baccarat::{
+    bet_sizing::Stake,
+    calculation::{Counter, Solution},
+    counting::CountingSystem,
+    game::HandsBet,
+};
 use yew::prelude::*;
 
 #[derive(Properties, PartialEq)]
 pub struct PropsInsights {
     pub solution: Solution,
+    /// The optimal stakes of all bets together, as fractions of the bankroll.
+    pub stakes: Vec<Stake>,
+    pub card_count: Vec<u32>,
+    pub number_of_decks: u32,
+    /// The proportion of the shoe dealt before the cut card.
+    pub penetration: f64,
 }
 
+const TIE_ON_NAMES: [&str; 10] = [
+    "Tie on 0", "Tie on 1", "Tie on 2", "Tie on 3", "Tie on 4", "Tie on 5", "Tie on 6", "Tie on 7",
+    "Tie on 8", "Tie on 9",
+];
+
 // Probability And Ex. In short, PEX.
 #[derive(Debug, Clone)]
-struct Pex(&'static str, f64, f64);
+struct Pex<'a>(&'a str, f64, f64, HandsBet);
 
 #[function_component]
 pub fn InsightsDiv(props: &PropsInsights) -> Html {
@@ -19,17 +35,43 @@ pub fn InsightsDiv(props: &PropsInsights) -> Html {
     let pu = &sb.p_player_bonus_unnatural;
     let bu = &sb.p_banker_bonus_unnatural;
 
-    let bets = vec![
-        Pex("Player Win", sm.p_player_win + sm.p_tie, sm.ex_player_win),
-        Pex("Banker Win", sm.p_banker_win + sm.p_tie, sm.ex_banker_win),
-        Pex("Tie", sm.p_tie, sm.ex_tie),
-        Pex("Player Pair", sp.p_unsuit_pair, sp.ex_unsuit_pair),
-        Pex("Banker Pair", sp.p_unsuit_pair, sp.ex_unsuit_pair),
-        Pex("Either Pair", sp.p_either_pair, sp.ex_either_pair),
+    let mut bets = vec![
+        Pex(
+            "Player Win",
+            sm.p_player_win + sm.p_tie,
+            sm.ex_player_win,
+            HandsBet::PlayerWin,
+        ),
+        Pex(
+            "Banker Win",
+            sm.p_banker_win + sm.p_tie,
+            sm.ex_banker_win,
+            HandsBet::BankerWin,
+        ),
+        Pex("Tie", sm.p_tie, sm.ex_tie, HandsBet::Tie),
+        Pex(
+            "Player Pair",
+            sp.p_unsuit_pair,
+            sp.ex_unsuit_pair,
+            HandsBet::PlayerUnsuitPair,
+        ),
+        Pex(
+            "Banker Pair",
+            sp.p_unsuit_pair,
+            sp.ex_unsuit_pair,
+            HandsBet::BankerUnsuitPair,
+        ),
+        Pex(
+            "Either Pair",
+            sp.p_either_pair,
+            sp.ex_either_pair,
+            HandsBet::EitherPair,
+        ),
         Pex(
             "Perfect Pair",
             sp.p_suit_pair[0] + sp.p_suit_pair[1],
             sp.ex_suit_pair,
+            HandsBet::PerfectPair,
         ),
         Pex(
             "Player Bonus",
@@ -37,6 +79,7 @@ pub fn InsightsDiv(props: &PropsInsights) -> Html {
                 + sb.p_player_bonus_natural_win
                 + sb.p_bonus_natural_tie,
             sb.ex_player_bonus,
+            HandsBet::PlayerBonus,
         ),
         Pex(
             "Banker Bonus",
@@ -44,9 +87,74 @@ pub fn InsightsDiv(props: &PropsInsights) -> Html {
                 + sb.p_banker_bonus_natural_win
                 + sb.p_bonus_natural_tie,
             sb.ex_banker_bonus,
+            HandsBet::BankerBonus,
         ),
-        Pex("No Bet", 1.0, 0.0),
     ];
+    let mut extra_bets = vec![];
+    if let Some(ex_tie_on_total) = &sm.ex_tie_on_total {
+        for (total, name) in TIE_ON_NAMES.iter().enumerate() {
+            let (p, ex) = (sm.p_tie_on_total[total], ex_tie_on_total[total]);
+            bets.push(Pex(name, p, ex, HandsBet::TieOn(total as u8)));
+            extra_bets.push(ExtraBet(name, vec![(name.to_string(), p)], ex));
+        }
+    }
+    if let Some(se) = &solution.sol_ez {
+        bets.push(Pex(
+            "Dragon 7",
+            se.p_dragon_7,
+            se.ex_dragon_7,
+            HandsBet::Dragon7,
+        ));
+        bets.push(Pex(
+            "Panda 8",
+            se.p_panda_8,
+            se.ex_panda_8,
+            HandsBet::Panda8,
+        ));
+        extra_bets.push(ExtraBet(
+            "Dragon 7",
+            vec![("Dragon 7".into(), se.p_dragon_7)],
+            se.ex_dragon_7,
+        ));
+        extra_bets.push(ExtraBet(
+            "Panda 8",
+            vec![("Panda 8".into(), se.p_panda_8)],
+            se.ex_panda_8,
+        ));
+    }
+    if let Some(s6) = &solution.sol_super_6 {
+        let p: f64 = s6.p_super_6.iter().sum();
+        bets.push(Pex("Super 6", p, s6.ex_super_6, HandsBet::Super6));
+        let results = vec![
+            ("Two-card 6".into(), s6.p_super_6[0]),
+            ("Three-card 6".into(), s6.p_super_6[1]),
+        ];
+        extra_bets.push(ExtraBet("Super 6", results, s6.ex_super_6));
+    }
+    if let Some(bs) = &solution.sol_big_small {
+        bets.push(Pex("Big", bs.p_big, bs.ex_big, HandsBet::Big));
+        bets.push(Pex("Small", bs.p_small, bs.ex_small, HandsBet::Small));
+        extra_bets.push(ExtraBet(
+            "Big",
+            vec![("5 or 6 cards".into(), bs.p_big)],
+            bs.ex_big,
+        ));
+        extra_bets.push(ExtraBet(
+            "Small",
+            vec![("4 cards".into(), bs.p_small)],
+            bs.ex_small,
+        ));
+    }
+    for (i, s) in solution.sol_side_bets.iter().enumerate() {
+        bets.push(Pex(&s.name, s.p.iter().sum(), s.ex, HandsBet::SideBet(i)));
+        let outcomes =
+            s.p.iter()
+                .enumerate()
+                .map(|(j, p)| (format!("Outcome {}", j + 1), *p))
+                .collect();
+        extra_bets.push(ExtraBet(&s.name, outcomes, s.ex));
+    }
+    bets.push(Pex("No Bet", 1.0, 0.0, HandsBet::PlaceHolder));
 
     let mut bets_main_p = bets[0..3].to_vec();
     let mut bets_main_ex = bets_main_p.clone();
@@ -54,7 +162,7 @@ pub fn InsightsDiv(props: &PropsInsights) -> Html {
     bets_main_ex.sort_by(|x, y| y.2.partial_cmp(&x.2).unwrap());
     bets_main_p.sort_by(|x, y| y.1.partial_cmp(&x.1).unwrap());
 
-    let mut bets_side_p = bets[3..9].to_vec();
+    let mut bets_side_p = bets[3..bets.len() - 1].to_vec();
     let mut bets_side_ex = bets_side_p.clone();
     bets_side_ex.push(bets.last().unwrap().clone());
     bets_side_ex.sort_by(|x, y| y.2.partial_cmp(&x.2).unwrap());
@@ -64,7 +172,9 @@ pub fn InsightsDiv(props: &PropsInsights) -> Html {
         <div id="insights">
             <table id="suggestion">
                 <tr> <th>{"Goal"}</th> <th>{"1st"}</th> <th>{"2nd"}</th> <th>{"3rd"}</th> </tr>
-                {best_suggestion_row(solution, &bets)}
+                {best_suggestion_row(&props.stakes, &bets)}
+                {true_count_row(&props.card_count, props.number_of_decks)}
+                {rounds_remaining_row(solution, &props.card_count, props.number_of_decks, props.penetration)}
                 {suggestion_row("Main bet (EX)", &bets_main_ex)}
                 {suggestion_row("Side bet (EX)", &bets_side_ex)}
                 {suggestion_row("Main bet (P)", &bets_main_p)}
@@ -72,54 +182,120 @@ pub fn InsightsDiv(props: &PropsInsights) -> Html {
             </table>
             <table id="stat1"><thead><tr><th>{"Bet"}</th><th>{"Result"}</th><th>{"P"}</th><th>{"Total P"}</th><th>{"Ex"}</th></tr></thead><tbody><tr><td rowspan="2">{"Player Win"}</td><td>{"Player Win"}</td><td>{f(sm.p_player_win)}</td><td rowspan="2">{f(bets[0].1)}</td><td rowspan="2">{g(bets[0].2)}</td></tr><tr><td>{"Tie"}</td><td>{f(sm.p_tie)}</td></tr><tr><td rowspan="2">{"Banker Win"}</td><td>{"Banker Win"}</td><td>{f(sm.p_banker_win)}</td><td rowspan="2">{f(bets[1].1)}</td><td rowspan="2">{g(bets[1].2)}</td></tr><tr><td>{"Tie"}</td><td>{f(sm.p_tie)}</td></tr><tr><td>{"Tie"}</td><td>{"Tie"}</td><td>{f(sm.p_tie)}</td><td>{f(bets[2].1)}</td><td>{g(bets[2].2)}</td></tr><tr><td>{"Player Pair"}</td><td>{"Player Pair"}</td><td>{f(sp.p_unsuit_pair)}</td><td>{f(bets[3].1)}</td><td>{g(bets[3].2)}</td></tr><tr><td>{"Banker Pair"}</td><td>{"Banker Pair"}</td><td>{f(sp.p_unsuit_pair)}</td><td>{f(bets[4].1)}</td><td>{g(bets[4].2)}</td></tr><tr><td>{"Either Pair"}</td><td>{"Either Pair"}</td><td>{f(sp.p_either_pair)}</td><td>{f(bets[5].1)}</td><td>{g(bets[5].2)}</td></tr><tr><td rowspan="2">{"Perfect Pair"}</td><td>{"Either Perfect"}</td><td>{f(sp.p_suit_pair[0])}</td><td rowspan="2">{f(bets[6].1)}</td><td rowspan="2">{g(bets[6].2)}</td></tr><tr><td>{"Both Perfect"}</td><td>{f(sp.p_suit_pair[1])}</td></tr></tbody></table>
             <table id="stat2"><thead><tr><th>{"Bet"}</th><th>{"Result"}</th><th>{"P"}</th><th>{"Total P"}</th><th>{"Ex"}</th></tr></thead><tbody><tr><td rowspan="8">{"Player Bonus"}</td><td>{"Bonus 4"}</td><td>{f(pu[0])}</td><td rowspan="8">{f(bets[7].1)}</td><td rowspan="8">{g(bets[7].2)}</td></tr><tr><td>{"Bonus 5"}</td><td>{f(pu[1])}</td></tr><tr><td>{"Bonus 6"}</td><td>{f(pu[2])}</td></tr><tr><td>{"Bonus 7"}</td><td>{f(pu[3])}</td></tr><tr><td>{"Bonus 8"}</td><td>{f(pu[4])}</td></tr><tr><td>{"Bonus 9"}</td><td>{f(pu[5])}</td></tr><tr><td>{"P Natural Win"}</td><td>{f(sb.p_player_bonus_natural_win)}</td></tr><tr><td>{"Natural Tie"}</td><td>{f(sb.p_bonus_natural_tie)}</td></tr><tr><td rowspan="8">{"Banker Bonus"}</td><td>{"Bonus 4"}</td><td>{f(bu[0])}</td><td rowspan="8">{f(bets[8].1)}</td><td rowspan="8">{g(bets[8].2)}</td></tr><tr><td>{"Bonus 5"}</td><td>{f(bu[1])}</td></tr><tr><td>{"Bonus 6"}</td><td>{f(bu[2])}</td></tr><tr><td>{"Bonus 7"}</td><td>{f(bu[3])}</td></tr><tr><td>{"Bonus 8"}</td><td>{f(bu[4])}</td></tr><tr><td>{"Bonus 9"}</td><td>{f(bu[5])}</td></tr><tr><td>{"B Natural Win"}</td><td>{f(sb.p_banker_bonus_natural_win)}</td></tr><tr><td>{"Natural Tie"}</td><td>{f(sb.p_bonus_natural_tie)}</td></tr></tbody></table>
+            {extra_bets_table(&extra_bets)}
         </div>
     }
 }
 
-fn best_suggestion_row(solution: &Solution, bets: &[Pex]) -> Html {
-    let max_ex = solution.get_best_main_side_bet(0.0);
-
-    fn hands_bet_to_pex_index(hands_bet: baccarat::game::HandsBet) -> usize {
-        match hands_bet {
-            baccarat::game::HandsBet::PlayerWin => 0,
-            baccarat::game::HandsBet::BankerWin => 1,
-            baccarat::game::HandsBet::Tie => 2,
-            baccarat::game::HandsBet::PlayerUnsuitPair => 3,
-            baccarat::game::HandsBet::BankerUnsuitPair => 4,
-            baccarat::game::HandsBet::EitherPair => 5,
-            baccarat::game::HandsBet::PerfectPair => 6,
-            baccarat::game::HandsBet::PlayerBonus => 7,
-            baccarat::game::HandsBet::BankerBonus => 8,
-            baccarat::game::HandsBet::PlaceHolder => 9,
-        }
+fn best_suggestion_row(stakes: &[Stake], bets: &[Pex]) -> Html {
+    let no_bet = bets.last().unwrap();
+    let find_pex = |hands_bet| bets.iter().find(|b| b.3 == hands_bet).unwrap_or(no_bet);
+    let (content_bet, content_ex) = if stakes.is_empty() {
+        (String::from("No Bet"), String::from(""))
+    } else {
+        let content_bet = stakes
+            .iter()
+            .map(|s| format!("<{}> {}", find_pex(s.bet).0, f(s.stake)))
+            .collect::<Vec<_>>()
+            .join(" + ");
+        let ex: f64 = stakes.iter().map(|s| s.stake * find_pex(s.bet).2).sum();
+        (content_bet, format!(" of bankroll [Ex: {}]", g(ex)))
+    };
+
+    html! {
+        <tr>
+            <td>{"Kelly"}</td>
+            <td colspan="3"><strong>{content_bet}</strong>{content_ex}</td>
+        </tr>
     }
+}
 
-    let main_pex = &bets[hands_bet_to_pex_index(max_ex.0 .0)];
-    let side_pex = &bets[hands_bet_to_pex_index(max_ex.1 .0)];
-    let (content_bet, content_ex) = {
-        if side_pex.2 < main_pex.2 && main_pex.2 > 0.0 {
-            (
-                format!("<{}>", main_pex.0),
-                format!(" [Ex: {}]", g(main_pex.2)),
-            )
-        } else if side_pex.2 > main_pex.2 && 2.0 * main_pex.2 + side_pex.2 > 0.0 {
-            (
-                format!("<{}>(2x) + <{}>(1x)", main_pex.0, side_pex.0),
-                format!(" [{:.3} / 300]", 2.0 * main_pex.2 + side_pex.2),
-            )
-        } else {
-            (String::from("No Bet"), String::from(""))
-        }
+fn true_count_row(card_count: &[u32], number_of_decks: u32) -> Html {
+    let Ok(counter) = Counter::try_with_card_count(card_count) else {
+        return html! {};
     };
+    let counts = CountingSystem::presets()
+        .iter()
+        .chain(&CountingSystem::eor_counts())
+        .map(|system| {
+            let true_count = system.get_true_count(&counter, number_of_decks);
+            format!("{}: {:+.2}", system.name, true_count)
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
 
     html! {
         <tr>
-            <td>{"Best Ex"}</td>
-            <td colspan="3"><strong>{content_bet}</strong>{content_ex}</td>
+            <td>{"True count"}</td>
+            <td colspan="3">{counts}</td>
         </tr>
     }
 }
 
+fn rounds_remaining_row(
+    solution: &Solution,
+    card_count: &[u32],
+    number_of_decks: u32,
+    penetration: f64,
+) -> Html {
+    let cards_per_round = solution
+        .p_cards
+        .iter()
+        .zip(["4", "5", "6"])
+        .map(|(p, cards)| format!("{} cards: {}", cards, f(*p)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // Only the cards entered are known to be dealt, so burned cards make the
+    // estimate a bit optimistic.
+    let shoe_size = number_of_decks * 52;
+    let cards_dealt = shoe_size.saturating_sub(card_count.iter().sum());
+    let cut_card = (shoe_size as f64 * penetration) as u32;
+    let cards_before_cut = cut_card.saturating_sub(cards_dealt);
+    let rounds = solution.get_expected_rounds_remaining(cards_before_cut);
+
+    html! {
+        <tr>
+            <td>{"Next round"}</td>
+            <td colspan="3">{cards_per_round}<br/>{format!("About {:.1} rounds left before the cut card ({} cards)", rounds, cards_before_cut)}</td>
+        </tr>
+    }
+}
+
+// A bet shown in the extra table, with its name, possible results and ex.
+struct ExtraBet<'a>(&'a str, Vec<(String, f64)>, f64);
+
+fn extra_bets_table(extra_bets: &[ExtraBet]) -> Html {
+    if extra_bets.is_empty() {
+        return html! {};
+    }
+
+    let rows = extra_bets.iter().map(|ExtraBet(name, results, ex)| {
+        let n = results.len().to_string();
+        let total_p = f(results.iter().map(|r| r.1).sum());
+        let results = results.iter().enumerate().map(|(i, (result, p))| {
+            let result = html! { <><td>{result}</td><td>{f(*p)}</td></> };
+            if i == 0 {
+                html! {
+                    <tr>
+                        <td rowspan={n.clone()}>{*name}</td>
+                        {result}
+                        <td rowspan={n.clone()}>{total_p.clone()}</td>
+                        <td rowspan={n.clone()}>{g(*ex)}</td>
+                    </tr>
+                }
+            } else {
+                html! { <tr>{result}</tr> }
+            }
+        });
+        html! { {for results} }
+    });
+
+    html! {
+        <table id="stat3"><thead><tr><th>{"Bet"}</th><th>{"Result"}</th><th>{"P"}</th><th>{"Total P"}</th><th>{"Ex"}</th></tr></thead><tbody>{for rows}</tbody></table>
+    }
+}
+
 fn suggestion_row(name: &str, bets: &[Pex]) -> Html {
     fn h(bet: &Pex) -> Html {
         html! {
@@ -3,12 +3,13 @@ mod help;
 mod insights;
 mod undolist;
 
-use baccarat::calculation::Solution;
-use gloo_console;
-use gloo_net;
+use baccarat::{
+    bet_sizing::{Stake, StakeOptions},
+    calculation::Solution,
+};
 use gloo_storage::{LocalStorage, Storage};
 use gloo_timers::callback::Timeout;
-use wasm_bindgen_futures;
+use serde::Serialize;
 use yew::prelude::*;
 
 const SUIT_EMOJIS: [&str; 4] = ["♦️", "♣️", "♥️", "♠️"];
@@ -17,32 +18,44 @@ const VALUE_CHARS: [char; 13] = [
 ];
 const NUMBER_OF_DECKS: &str = "number_of_stacks";
 const COUNTER: &str = "counter";
+const PENETRATION: &str = "penetration";
+const DEFAULT_PENETRATION: f64 = 0.85;
 
 const API_SOLVE: &str = "/api/solve";
+const API_OPTIMAL_BETS: &str = "/api/optimal_bets";
 
 #[derive(Debug, Clone)]
-struct UndoListItem(Vec<u32>, Solution);
+struct UndoListItem(Vec<u32>, Solution, Vec<Stake>);
+
+#[derive(Serialize)]
+struct OptimalBetsRequest<'a> {
+    card_count: &'a [u32],
+    options: StakeOptions,
+}
 type UndoList = undolist::UndoList<UndoListItem, 100>;
 
 #[function_component]
 fn App() -> Html {
     let number_of_decks = use_state(|| 8);
+    let penetration = use_state(|| DEFAULT_PENETRATION);
     let counter_display = use_state(|| vec![8; 52]);
     let hint = use_state(|| String::from(""));
     let is_requesting = use_state(|| false);
     let counter_try = use_state(|| vec![8; 52]);
     let retry_times = use_state(|| 1u32);
-    let solution: UseStateHandle<Solution> = use_state(|| Default::default());
-    let undo_list = use_state(|| UndoList::new());
+    let solution: UseStateHandle<Solution> = use_state(Default::default);
+    let stakes: UseStateHandle<Vec<Stake>> = use_state(Vec::new);
+    let undo_list = use_state(UndoList::new);
     let show_help = use_state(|| false);
     let reset_pressing = use_state(|| false);
     let show_decks_edit = use_state(|| false);
     let timer_true_trigger = use_state(|| false);
     let timeout: UseStateHandle<Option<Timeout>> = use_state(|| None);
 
-    // Effect for (counter_try, retry_times) -> (solution, is_requesting, hint, counter_display, undo_list)
+    // Effect for (counter_try, retry_times) -> (solution, stakes, is_requesting, hint, counter_display, undo_list)
     {
         let solution = solution.clone();
+        let stakes = stakes.clone();
         let counter_try = counter_try.clone();
         let counter_display = counter_display.clone();
         let retry_times = retry_times.clone();
@@ -65,8 +78,7 @@ fn App() -> Html {
             if let Err(ref e) = response {
                 hint.set(format!(
                     "({}) Cannot get response from server: {}",
-                    *retry_times,
-                    e.to_string()
+                    *retry_times, e
                 ));
                 return;
             }
@@ -85,16 +97,19 @@ fn App() -> Html {
             retry_times.set(0);
 
             let fetched_solution: Solution = response.json().await.unwrap();
+            let fetched_stakes = fetch_optimal_bets(&counter_try).await;
             let mut new_undo_list = (*undo_list).clone();
             new_undo_list.append(UndoListItem(
                 (*counter_try).clone(),
                 fetched_solution.clone(),
+                fetched_stakes.clone(),
             ));
             solution.set(fetched_solution);
+            stakes.set(fetched_stakes);
             undo_list.set(new_undo_list);
 
             counter_display.set((*counter_try).clone());
-            if let Err(_) = <LocalStorage as Storage>::set(COUNTER, (*counter_try).clone()) {
+            if <LocalStorage as Storage>::set(COUNTER, (*counter_try).clone()).is_err() {
                 panic!("Cannot set local storage!");
             }
         };
@@ -114,6 +129,7 @@ fn App() -> Html {
     // Initialize from local storage.
     {
         let number_of_decks = number_of_decks.clone();
+        let penetration = penetration.clone();
         let counter_display = counter_display.clone();
         let counter_try = counter_try.clone();
         use_effect_with_deps(
@@ -132,12 +148,16 @@ fn App() -> Html {
                         );
                         gloo_console::warn!(msg);
                         current_number_of_decks = 8;
-                        if let Err(_) = <LocalStorage as Storage>::set(NUMBER_OF_DECKS, 8) {
+                        if <LocalStorage as Storage>::set(NUMBER_OF_DECKS, 8).is_err() {
                             panic!("Cannot set local storage!");
                         }
                     }
                 }
 
+                if let Ok(v) = <LocalStorage as Storage>::get::<f64>(PENETRATION) {
+                    penetration.set(v);
+                }
+
                 match <LocalStorage as Storage>::get::<Vec<u32>>(COUNTER) {
                     Ok(v) => {
                         counter_display.set(v.clone());
@@ -147,10 +167,12 @@ fn App() -> Html {
                         let msg =
                             format!("'{}' not found in local storage. Default is used", COUNTER);
                         gloo_console::warn!(msg);
-                        if let Err(_) = <LocalStorage as Storage>::set(
+                        if <LocalStorage as Storage>::set(
                             COUNTER,
                             vec![current_number_of_decks; 52],
-                        ) {
+                        )
+                        .is_err()
+                        {
                             panic!("Cannot set local storage!");
                         }
                     }
@@ -194,7 +216,7 @@ fn App() -> Html {
         retry_times.clone(),
     );
 
-    let (hint_msg, hint_cls) = if hint.len() == 0 {
+    let (hint_msg, hint_cls) = if hint.is_empty() {
         (String::from("x"), "invisible")
     } else {
         ((*hint).clone(), "")
@@ -205,6 +227,7 @@ fn App() -> Html {
         let hint = hint.clone();
         let counter_display = counter_display.clone();
         let solution = solution.clone();
+        let stakes = stakes.clone();
         move |_| {
             let mut new_undo_list = (*undo_list).clone();
             let prev_state = new_undo_list.undo();
@@ -212,17 +235,18 @@ fn App() -> Html {
                 hint.set(String::from("Cannot undo anymore"));
                 return;
             }
-            if hint.len() != 0 {
+            if !hint.is_empty() {
                 hint.set(String::from(""));
             }
             let prev_state = prev_state.unwrap();
             undo_list.set(new_undo_list);
 
-            if let Err(_) = <LocalStorage as Storage>::set(COUNTER, prev_state.0.clone()) {
+            if <LocalStorage as Storage>::set(COUNTER, prev_state.0.clone()).is_err() {
                 panic!("Cannot set local storage!");
             }
             counter_display.set(prev_state.0);
             solution.set(prev_state.1);
+            stakes.set(prev_state.2);
         }
     };
 
@@ -231,6 +255,7 @@ fn App() -> Html {
         let hint = hint.clone();
         let counter_display = counter_display.clone();
         let solution = solution.clone();
+        let stakes = stakes.clone();
         move |_| {
             let mut new_undo_list = (*undo_list).clone();
             let next_state = new_undo_list.redo();
@@ -238,17 +263,18 @@ fn App() -> Html {
                 hint.set(String::from("Cannot redo anymore"));
                 return;
             }
-            if hint.len() != 0 {
+            if !hint.is_empty() {
                 hint.set(String::from(""));
             }
             let next_state = next_state.unwrap();
             undo_list.set(new_undo_list);
 
-            if let Err(_) = <LocalStorage as Storage>::set(COUNTER, next_state.0.clone()) {
+            if <LocalStorage as Storage>::set(COUNTER, next_state.0.clone()).is_err() {
                 panic!("Cannot set local storage!");
             }
             counter_display.set(next_state.0);
             solution.set(next_state.1);
+            stakes.set(next_state.2);
         }
     };
 
@@ -266,7 +292,7 @@ fn App() -> Html {
                 ));
                 return;
             }
-            if hint.len() != 0 {
+            if !hint.is_empty() {
                 hint.set(String::from(""));
             }
             let new_counter = vec![*number_of_decks; 52];
@@ -304,7 +330,7 @@ fn App() -> Html {
         let hint = hint.clone();
         move |_| {
             show_help.set(true);
-            if hint.len() != 0 {
+            if !hint.is_empty() {
                 hint.set(String::from(""));
             }
         }
@@ -320,22 +346,36 @@ fn App() -> Html {
     let onconfirm_decks_edit = {
         let show_decks_edit = show_decks_edit.clone();
         let number_of_decks = number_of_decks.clone();
+        let penetration = penetration.clone();
         let hint = hint.clone();
-        move |new_number_of_decks| {
+        move |(new_number_of_decks, new_penetration): (u32, f64)| {
             show_decks_edit.set(false);
-            if new_number_of_decks <= 0 {
+            if new_number_of_decks == 0 {
                 hint.set(String::from("Number of decks must be positive."));
                 return;
             }
-            if new_number_of_decks == *number_of_decks {
+            if !(0.0..=1.0).contains(&new_penetration) {
+                hint.set(String::from("Cut card must be between 0% and 100%."));
+                return;
+            }
+            if new_number_of_decks == *number_of_decks && new_penetration == *penetration {
                 hint.set(String::from("No change"));
                 return;
             }
-            if hint.len() != 0 {
+            if !hint.is_empty() {
                 hint.set(String::from(""));
             }
+            if new_penetration != *penetration {
+                penetration.set(new_penetration);
+                if <LocalStorage as Storage>::set(PENETRATION, new_penetration).is_err() {
+                    panic!("Cannot set local storage!");
+                }
+            }
+            if new_number_of_decks == *number_of_decks {
+                return;
+            }
             number_of_decks.set(new_number_of_decks);
-            if let Err(_) = <LocalStorage as Storage>::set(NUMBER_OF_DECKS, new_number_of_decks) {
+            if <LocalStorage as Storage>::set(NUMBER_OF_DECKS, new_number_of_decks).is_err() {
                 panic!("Cannot set local storage!");
             }
         }
@@ -353,19 +393,40 @@ fn App() -> Html {
                 <button id="help" title="Help" type="button" onclick={Callback::from(onclick_help)}>{"?"}</button>
             </div>
 
-            <insights::InsightsDiv solution={(*solution).clone()} />
+            <insights::InsightsDiv solution={(*solution).clone()} stakes={(*stakes).clone()} card_count={(*counter_display).clone()} number_of_decks={*number_of_decks} penetration={*penetration} />
 
             if *show_help {
                 <help::HelpDiv on_close={Callback::from(onclose_help)} />
             }
 
             if *show_decks_edit {
-                <decks_edit::DecksEdit on_confirm={Callback::from(onconfirm_decks_edit)} initial_num={*number_of_decks} />
+                <decks_edit::DecksEdit on_confirm={Callback::from(onconfirm_decks_edit)} initial_num={*number_of_decks} initial_penetration={*penetration} />
             }
         </>
     }
 }
 
+/// Fetches the Kelly stakes of all the offered bets together, as fractions of
+/// the bankroll. No bet is suggested if anything goes wrong.
+async fn fetch_optimal_bets(card_count: &[u32]) -> Vec<Stake> {
+    let request = OptimalBetsRequest {
+        card_count,
+        options: Default::default(),
+    };
+    let response = gloo_net::http::Request::post(API_OPTIMAL_BETS)
+        .json(&request)
+        .unwrap()
+        .send()
+        .await;
+    match response {
+        Ok(response) if response.ok() => response.json().await.unwrap_or_default(),
+        _ => {
+            gloo_console::warn!("Cannot get optimal bets from server");
+            vec![]
+        }
+    }
+}
+
 fn convert_all_cards_to_html(
     card_names: &[[String; 13]; 4],
     counter_display: UseStateHandle<Vec<u32>>,
@@ -448,7 +509,7 @@ fn convert_name_to_html(
             hint.set(already_zero.clone());
             return;
         }
-        if hint.len() != 0 {
+        if !hint.is_empty() {
             hint.set(String::from(""));
         }
         let mut new_counter = (*counter_display).clone();
@@ -0,0 +1,274 @@
+use crate::shoe_runner::{self, ShoeCounter, ThreadStats};
+use crate::simulation::{SimulatorCount, SimulatorDealer};
+use baccarat::bet_sizing;
+use baccarat::calculation::{effect_of_removal, incremental::IncrementalSolver, Counter, Solution};
+use baccarat::card::Card;
+use baccarat::counting::CountingSystem;
+use baccarat::game::{GamblerProvider, Game, Hand, HandsBet, RoundResult};
+use baccarat::Rule;
+use baccarat_drivers_lib::ConfigCounting;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// The money bet whenever the count says so. Win rates are in units of it.
+const STAKE: i64 = 100;
+
+/// Plays the shoes (in parallel) with the counting system, comparing its true
+/// count with the ex found by the solver in every round, and prints the
+/// report.
+pub fn evaluate_count(rule: &Rule, counting: &ConfigCounting, shoes: u64) {
+    if rule.chemin_de_fer {
+        panic!("Counting systems don't support Chemin de fer");
+    }
+    let system = counting.get_system();
+    let bets = bet_sizing::get_offered_bets(rule);
+
+    let stats = shoe_runner::run_shoes(shoes, |counter| {
+        evaluate_shoes(rule, &system, counting.threshold, &bets, counter)
+    });
+    print_report(rule, &system, counting.threshold, &bets, shoes, &stats);
+}
+
+fn evaluate_shoes(
+    rule: &Rule,
+    system: &CountingSystem,
+    threshold: f64,
+    bets: &[HandsBet],
+    counter: &ShoeCounter,
+) -> EvaluationStats {
+    let firsts = RefCell::new(None);
+    let stats = RefCell::new(EvaluationStats::new(bets.len()));
+    let dealer = SimulatorDealer::new(rule.number_of_decks, rule.cut_card_proportion, &firsts);
+    let gambler = EvaluationGambler {
+        rule,
+        offered_bets: bets,
+        bet: system.bet.unwrap(),
+        count: SimulatorCount::new(system, rule.number_of_decks, threshold),
+        counter,
+        stats: &stats,
+        bets: HashMap::new(),
+    };
+    let solver = IncrementalSolver::new(rule, &Counter::new(rule.number_of_decks));
+    let mut game = Game::new(rule, dealer, gambler, solver);
+    counter.play(|| game.play_round());
+    drop(game);
+    stats.into_inner()
+}
+
+/// Online Pearson correlation of (x, y) pairs.
+#[derive(Debug, Clone, Default)]
+struct Correlation {
+    n: f64,
+    sx: f64,
+    sy: f64,
+    sxx: f64,
+    syy: f64,
+    sxy: f64,
+}
+
+impl Correlation {
+    fn add(&mut self, x: f64, y: f64) {
+        self.n += 1.0;
+        self.sx += x;
+        self.sy += y;
+        self.sxx += x * x;
+        self.syy += y * y;
+        self.sxy += x * y;
+    }
+
+    fn merge(self, other: Self) -> Self {
+        Self {
+            n: self.n + other.n,
+            sx: self.sx + other.sx,
+            sy: self.sy + other.sy,
+            sxx: self.sxx + other.sxx,
+            syy: self.syy + other.syy,
+            sxy: self.sxy + other.sxy,
+        }
+    }
+
+    /// Returns None if either x or y never changes.
+    fn get(&self) -> Option<f64> {
+        let var_x = self.n * self.sxx - self.sx * self.sx;
+        let var_y = self.n * self.syy - self.sy * self.sy;
+        if var_x <= 0.0 || var_y <= 0.0 {
+            return None;
+        }
+        Some((self.n * self.sxy - self.sx * self.sy) / (var_x * var_y).sqrt())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EvaluationStats {
+    rounds: u64,
+    /// The correlation of the true count with the ex of each offered bet.
+    correlations: Vec<Correlation>,
+
+    // The rest are about the bet of the counting system.
+    positive_rounds: u64,
+    count_rounds: u64,
+    count_positive_rounds: u64,
+    /// Sum of the ex of the rounds the count bets.
+    count_ex: f64,
+    /// Sum of the ex of the rounds the solver bets, i.e. those with positive
+    /// ex.
+    solver_ex: f64,
+    count_profit: i64,
+}
+
+impl EvaluationStats {
+    fn new(bets: usize) -> Self {
+        Self {
+            rounds: 0,
+            correlations: vec![Default::default(); bets],
+            positive_rounds: 0,
+            count_rounds: 0,
+            count_positive_rounds: 0,
+            count_ex: 0.0,
+            solver_ex: 0.0,
+            count_profit: 0,
+        }
+    }
+}
+
+impl ThreadStats for EvaluationStats {
+    fn merge(self, other: Self) -> Self {
+        Self {
+            rounds: self.rounds + other.rounds,
+            correlations: self
+                .correlations
+                .into_iter()
+                .zip(other.correlations)
+                .map(|(x, y)| x.merge(y))
+                .collect(),
+            positive_rounds: self.positive_rounds + other.positive_rounds,
+            count_rounds: self.count_rounds + other.count_rounds,
+            count_positive_rounds: self.count_positive_rounds + other.count_positive_rounds,
+            count_ex: self.count_ex + other.count_ex,
+            solver_ex: self.solver_ex + other.solver_ex,
+            count_profit: self.count_profit + other.count_profit,
+        }
+    }
+}
+
+struct EvaluationGambler<'a> {
+    rule: &'a Rule,
+    offered_bets: &'a [HandsBet],
+    bet: HandsBet, // The bet of the counting system.
+    count: SimulatorCount<'a>,
+    counter: &'a ShoeCounter,
+    stats: &'a RefCell<EvaluationStats>,
+
+    bets: HashMap<HandsBet, i64>,
+}
+
+impl<'a> GamblerProvider for EvaluationGambler<'a> {
+    fn place_bet(&mut self, solution: &Solution) -> &HashMap<HandsBet, i64> {
+        self.bets.clear();
+        if !self.counter.is_recording() {
+            return &self.bets;
+        }
+        let mut stats = self.stats.borrow_mut();
+
+        let true_count = self.count.get_true_count();
+        stats.rounds += 1;
+        for (bet, correlation) in self.offered_bets.iter().zip(&mut stats.correlations) {
+            correlation.add(true_count, solution.get_ex(*bet).unwrap());
+        }
+
+        let bet = self.bet;
+        let ex = solution.get_ex(bet).unwrap_or(-1.0);
+        if ex > 0.0 {
+            stats.positive_rounds += 1;
+            stats.solver_ex += ex;
+        }
+        if self.count.is_betting() {
+            self.bets.insert(bet, STAKE);
+            stats.count_rounds += 1;
+            stats.count_ex += ex;
+            if ex > 0.0 {
+                stats.count_positive_rounds += 1;
+            }
+        }
+        &self.bets
+    }
+    fn on_new_shoe(&mut self) {
+        self.counter.start_shoe();
+        self.count.start_new_shoe(self.rule.number_of_decks);
+    }
+    fn on_discard(&mut self, card: Card) {
+        self.count.add_dealt_card(card);
+    }
+    fn on_round_start(&mut self) {}
+    fn on_round_end(&mut self, player: &Hand, banker: &Hand, round_result: &RoundResult) {
+        for hand in [player, banker] {
+            hand.initial
+                .iter()
+                .chain(&hand.third)
+                .for_each(|card| self.count.add_dealt_card(*card));
+        }
+        if !self.bets.is_empty() {
+            self.stats.borrow_mut().count_profit += round_result.total_profit;
+        }
+    }
+    fn on_cut_card_reached(&mut self, _cards_before_cut: u32) {}
+}
+
+fn print_report(
+    rule: &Rule,
+    system: &CountingSystem,
+    threshold: f64,
+    bets: &[HandsBet],
+    shoes: u64,
+    stats: &EvaluationStats,
+) {
+    let bet = system.bet.unwrap();
+    let rounds = stats.rounds as f64;
+    let percent = |x: u64| x as f64 / rounds * 100.0;
+    let per_100_rounds = |x: f64| x / rounds * 100.0;
+    let f = |x: Option<f64>| x.map_or(String::from("-"), |x| format!("{:.4}", x));
+
+    println!(
+        "Counting system {} on {:?}, betting at true count >= {}",
+        system.name, bet, threshold
+    );
+    println!("{} shoes, {} rounds", shoes, stats.rounds);
+    if let Ok(eor) = effect_of_removal::calculate(rule) {
+        println!(
+            "Betting correlation (tags vs effects of removal): {}",
+            f(system.get_betting_correlation(&eor, bet))
+        );
+    }
+
+    println!("Correlation of the true count with the ex:");
+    for (bet, correlation) in bets.iter().zip(&stats.correlations) {
+        println!("{:>16}: {}", format!("{:?}", bet), f(correlation.get()));
+    }
+
+    println!(
+        "Positive ex: {:.3}% of rounds",
+        percent(stats.positive_rounds)
+    );
+    println!(
+        "Count bets: {:.3}% of rounds, {:.2}% of which have positive ex",
+        percent(stats.count_rounds),
+        stats.count_positive_rounds as f64 / stats.count_rounds.max(1) as f64 * 100.0
+    );
+    let efficiency = (stats.solver_ex > 0.0).then(|| stats.count_ex / stats.solver_ex);
+    println!(
+        "Betting efficiency (ex of the count's bets / ex of the solver's bets): {}",
+        f(efficiency)
+    );
+    println!("Win rate per 100 rounds, in units of the stake:");
+    println!(
+        "{:>16}: {:.4} expected, {:.4} realized",
+        "Count",
+        per_100_rounds(stats.count_ex),
+        per_100_rounds(stats.count_profit as f64 / STAKE as f64)
+    );
+    println!(
+        "{:>16}: {:.4} expected",
+        "Solver",
+        per_100_rounds(stats.solver_ex)
+    );
+}
@@ -0,0 +1,148 @@
+use crate::run_rounds;
+use crate::simulation::SimulatorDealer;
+use baccarat::calculation::Counter;
+use baccarat::card::Card;
+use baccarat::dragon_tiger::calculation::{DragonTigerSolution, DragonTigerSolver};
+use baccarat::dragon_tiger::game::{
+    DragonTigerBet, DragonTigerGamblerProvider, DragonTigerGame, DragonTigerRoundResult,
+    DragonTigerSolverProvider,
+};
+use baccarat::dragon_tiger::DragonTigerRule;
+use baccarat_drivers_lib::{ConfigBaccaratSimulator, MoneyStat};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+pub fn start_simulation(
+    rule: &DragonTigerRule,
+    config: &ConfigBaccaratSimulator,
+    rounds: Option<u64>,
+) {
+    let firsts = RefCell::new(None);
+    let dealer = SimulatorDealer::new(rule.number_of_decks, rule.cut_card_proportion, &firsts);
+    let gambler = SimulatorGambler::new(config, &firsts);
+    let solver = SimulatorSolver::new(rule);
+    let mut game = DragonTigerGame::new(rule, dealer, gambler, solver);
+    run_rounds(rounds, || game.play_round());
+}
+
+#[derive(Debug, Clone)]
+struct SimulatorGambler<'a> {
+    config: &'a ConfigBaccaratSimulator,
+    firsts: &'a RefCell<Option<Vec<Card>>>,
+
+    bets: HashMap<DragonTigerBet, i64>,
+
+    rounds: u32,
+    bet_money: i64,
+
+    // Records for log
+    max_bet: (DragonTigerBet, f64),
+    money_stat: MoneyStat,
+}
+
+impl<'a> SimulatorGambler<'a> {
+    fn new(config: &'a ConfigBaccaratSimulator, firsts: &'a RefCell<Option<Vec<Card>>>) -> Self {
+        Self {
+            config,
+            firsts,
+
+            bets: HashMap::new(),
+
+            rounds: 0,
+            bet_money: 200,
+
+            max_bet: (DragonTigerBet::Dragon, -f64::INFINITY),
+            money_stat: Default::default(),
+        }
+    }
+
+    fn print_log(&self, delta_money: i64, dragon: Card, tiger: Card) {
+        println!(
+            "Day #{}, Round #{}",
+            self.rounds / self.config.rounds_per_day,
+            self.rounds % self.config.rounds_per_day
+        );
+
+        println!("Max bet: {:?}({:.5})", self.max_bet.0, self.max_bet.1);
+
+        print!("Bet:");
+        for (bet, money) in &self.bets {
+            print!(" ({:?}, {})", bet, money);
+        }
+        println!();
+
+        let mut firsts = self.firsts.borrow_mut();
+        let firsts = firsts.take().unwrap();
+        print!("First {} cards in shoe:", firsts.len());
+        for card in firsts {
+            print!(" {:#?}", card);
+        }
+        println!();
+
+        println!("Dragon: {:#?}. Tiger: {:#?}", dragon, tiger);
+
+        println!(
+            "Money: {}({}). Min: {}. Max: {}.",
+            self.money_stat.cur_money(),
+            delta_money,
+            self.money_stat.min_money(),
+            self.money_stat.max_money()
+        );
+
+        println!("----------------------------------");
+    }
+}
+
+impl<'a> DragonTigerGamblerProvider for SimulatorGambler<'a> {
+    fn on_new_shoe(&mut self) {
+        println!("NEW SHOE!!!!!!!");
+        println!("++++++++++++++++++++++++++++++++++");
+        let mut firsts = self.firsts.borrow_mut();
+        *firsts = None;
+    }
+    fn on_cut_card_reached(&mut self, _cards_before_cut: u32) {}
+    fn on_discard(&mut self, card: Card) {
+        println!("Discard: {:#?}", card);
+        println!("++++++++++++++++++++++++++++++++++");
+    }
+    fn on_round_start(&mut self) {
+        let mut firsts = self.firsts.borrow_mut();
+        *firsts = None;
+    }
+    fn place_bet(&mut self, solution: &DragonTigerSolution) -> &HashMap<DragonTigerBet, i64> {
+        self.bets.clear();
+
+        self.max_bet = solution.get_best_bet(self.config.p_threshold);
+        if self.max_bet.1 > 0.0 {
+            self.bets.insert(self.max_bet.0, self.bet_money);
+        }
+
+        &self.bets
+    }
+    fn on_round_end(&mut self, dragon: Card, tiger: Card, round_result: &DragonTigerRoundResult) {
+        self.money_stat.add(round_result.total_profit);
+        if !self.bets.is_empty() {
+            self.print_log(round_result.total_profit, dragon, tiger);
+        }
+        self.rounds += 1;
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SimulatorSolver<'a> {
+    solver: DragonTigerSolver<'a>,
+}
+
+impl<'a> SimulatorSolver<'a> {
+    fn new(rule: &'a DragonTigerRule) -> Self {
+        Self {
+            solver: DragonTigerSolver::new(rule),
+        }
+    }
+}
+
+impl<'a> DragonTigerSolverProvider for SimulatorSolver<'a> {
+    fn solve(&mut self, counter: &Counter) -> &DragonTigerSolution {
+        self.solver.solve(counter)
+    }
+}
@@ -0,0 +1,36 @@
+use baccarat::calculation::effect_of_removal;
+use baccarat::card::Card;
+use baccarat::Rule;
+
+const RANKS: [&str; 13] = [
+    "A", "2", "3", "4", "5", "6", "7", "8", "9", "T", "J", "Q", "K",
+];
+
+/// Prints the EOR tables of all the offered bets as CSV, first by rank and
+/// then by card. The EORs are in percent of the bet.
+pub fn print_effect_of_removal(rule: &Rule) {
+    let eor = match effect_of_removal::calculate(rule) {
+        Ok(eor) => eor,
+        Err(e) => panic!("Cannot calculate effect of removal: {}", e),
+    };
+
+    println!("bet,ex,{}", RANKS.join(","));
+    for (i, bet) in eor.bets.iter().enumerate() {
+        let row: Vec<String> = eor.by_rank.iter().map(|x| f(x[i])).collect();
+        println!("{:?},{},{}", bet, f(eor.ex[i]), row.join(","));
+    }
+    println!();
+
+    let cards: Vec<String> = (0..52)
+        .map(|c| format!("{:?}", Card::from_index(c)))
+        .collect();
+    println!("bet,ex,{}", cards.join(","));
+    for (i, bet) in eor.bets.iter().enumerate() {
+        let row: Vec<String> = eor.by_card.iter().map(|x| f(x[i])).collect();
+        println!("{:?},{},{}", bet, f(eor.ex[i]), row.join(","));
+    }
+}
+
+fn f(x: f64) -> String {
+    format!("{:.6}", x * 100.0)
+}
@@ -1,7 +1,13 @@
+mod count_evaluation;
+mod dragon_tiger_simulation;
+mod effect_of_removal;
+mod shoe_analysis;
+mod shoe_runner;
 mod simulation;
 
-use baccarat_drivers_lib::parse_config_from_file;
-use clap::Parser;
+use baccarat_drivers_lib::{parse_config_from_file, GameKind};
+use clap::{Parser, Subcommand};
+use std::time::Instant;
 
 const DEFAULT_CONFIG_PATH: &str = "~/.baccarat.yml";
 
@@ -12,6 +18,65 @@ struct CommandLineArgs {
     /// The path of the config file
     #[arg(short, long, default_value_t = String::from(DEFAULT_CONFIG_PATH))]
     config: String,
+
+    /// Stop after these many rounds and print how many rounds are simulated
+    /// per second
+    #[arg(short, long)]
+    rounds: Option<u64>,
+
+    /// Play these many independent sessions of --rounds rounds each, starting
+    /// with the configured bankroll, and print the risk of ruin and the
+    /// percentiles of the bankroll and the max drawdown
+    #[arg(short, long, requires = "rounds")]
+    sessions: Option<u32>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Print how much removing each card from a full shoe changes the ex of
+    /// every bet, as CSV tables by rank and by card
+    EffectOfRemoval,
+
+    /// Play the given number of shoes with the configured counting system,
+    /// and print how well its true count follows the ex found by the solver
+    EvaluateCount {
+        /// The number of shoes to play
+        #[arg(long)]
+        shoes: u64,
+    },
+
+    /// Play the given number of shoes with the configured betting policy, and
+    /// print the expected profit per shoe, and the rounds with positive ex by
+    /// the depth of the shoe as CSV
+    ShoeAnalysis {
+        /// The number of shoes to play
+        #[arg(long)]
+        shoes: u64,
+    },
+}
+
+/// Plays the given number of rounds and prints the speed, or plays forever
+/// if it's None.
+fn run_rounds<F: FnMut()>(rounds: Option<u64>, mut play_round: F) {
+    let Some(rounds) = rounds else {
+        loop {
+            play_round();
+        }
+    };
+    let start = Instant::now();
+    for _ in 0..rounds {
+        play_round();
+    }
+    let seconds = start.elapsed().as_secs_f64();
+    eprintln!(
+        "{} rounds in {:.2}s ({:.1} rounds/second)",
+        rounds,
+        seconds,
+        rounds as f64 / seconds
+    );
 }
 
 fn main() {
@@ -30,5 +95,47 @@ fn main() {
     let args = args;
 
     let config = parse_config_from_file(&args.config);
-    simulation::start_simulation(&config.rule, &config.baccarat_simulator);
+    if let Some(Command::EffectOfRemoval) = args.command {
+        if config.game != GameKind::Baccarat {
+            panic!("Effect of removal is only supported in Baccarat");
+        }
+        effect_of_removal::print_effect_of_removal(&config.rule);
+        return;
+    }
+    if let Some(Command::EvaluateCount { shoes }) = args.command {
+        if config.game != GameKind::Baccarat {
+            panic!("Counting systems are only supported in Baccarat");
+        }
+        let Some(counting) = &config.baccarat_simulator.counting else {
+            panic!("No counting system in the config");
+        };
+        count_evaluation::evaluate_count(&config.rule, counting, shoes);
+        return;
+    }
+    if let Some(Command::ShoeAnalysis { shoes }) = args.command {
+        if config.game != GameKind::Baccarat {
+            panic!("Shoe analysis is only supported in Baccarat");
+        }
+        shoe_analysis::analyze_shoes(&config.rule, &config.baccarat_simulator, shoes);
+        return;
+    }
+    if let Some(sessions) = args.sessions {
+        if config.game != GameKind::Baccarat {
+            panic!("Sessions are only supported in Baccarat");
+        }
+        let rounds = args.rounds.unwrap();
+        simulation::start_sessions(&config.rule, &config.baccarat_simulator, sessions, rounds);
+        return;
+    }
+
+    match config.game {
+        GameKind::Baccarat => {
+            simulation::start_simulation(&config.rule, &config.baccarat_simulator, args.rounds)
+        }
+        GameKind::DragonTiger => dragon_tiger_simulation::start_simulation(
+            &config.dragon_tiger_rule,
+            &config.baccarat_simulator,
+            args.rounds,
+        ),
+    }
 }
@@ -0,0 +1,273 @@
+use crate::shoe_runner::{self, ShoeCounter, ThreadStats};
+use crate::simulation::{
+    GamblerMode, SimulatorDealer, SimulatorDecider, SimulatorGambler, SimulatorSolver,
+};
+use baccarat::bet_sizing;
+use baccarat::calculation::{incremental::IncrementalSolver, Counter, Solution};
+use baccarat::card::Card;
+use baccarat::game::{GamblerProvider, Game, Hand, HandsBet, RoundResult};
+use baccarat::Rule;
+use baccarat_drivers_lib::{ConfigBaccaratSimulator, MoneyStat};
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// The depth curve has a point for every 1/DEPTH_BUCKETS of the shoe.
+const DEPTH_BUCKETS: usize = 20;
+
+/// Plays the shoes (in parallel) with the configured betting policy, solving
+/// every round exactly, and prints the expected profit per shoe and how the
+/// rounds with positive ex spread over the depth of the shoe.
+pub fn analyze_shoes(rule: &Rule, config: &ConfigBaccaratSimulator, shoes: u64) {
+    let bets = bet_sizing::get_offered_bets(rule);
+
+    let stats = shoe_runner::run_shoes(shoes, |counter| play_shoes(rule, config, &bets, counter));
+    print_report(rule, shoes, &stats);
+}
+
+fn play_shoes(
+    rule: &Rule,
+    config: &ConfigBaccaratSimulator,
+    bets: &[HandsBet],
+    counter: &ShoeCounter,
+) -> ShoeStats {
+    let firsts = RefCell::new(None);
+    let money_stat = RefCell::new(MoneyStat::default());
+    let system = config.counting.as_ref().map(|c| c.get_system());
+    let stats = RefCell::new(ShoeStats::default());
+    let dealer = SimulatorDealer::new(rule.number_of_decks, rule.cut_card_proportion, &firsts);
+    let gambler = ShoeGambler {
+        gambler: SimulatorGambler::new(
+            rule,
+            config,
+            &firsts,
+            &money_stat,
+            GamblerMode::Quiet,
+            system.as_ref(),
+        ),
+        rule,
+        offered_bets: bets,
+        counter,
+        stats: &stats,
+        shoe: Default::default(),
+    };
+    let strategy = RefCell::new(None);
+    let solver = IncrementalSolver::new(rule, &Counter::new(rule.number_of_decks));
+    let solver = SimulatorSolver::new(solver, &strategy);
+    let decider = SimulatorDecider::new(&strategy);
+    let mut game = Game::with_decision_provider(rule, dealer, gambler, solver, decider);
+    counter.play(|| game.play_round());
+    drop(game);
+    stats.into_inner()
+}
+
+#[derive(Debug, Clone, Default)]
+struct ShoeStats {
+    rounds: u64,
+    min_rounds: Option<u32>,
+    max_rounds: u32,
+    cards_dealt: u64,
+    cards_after_cut: u64,
+    expected_profit: f64,
+    realized_profit: i64,
+    realized_profit_squared: f64,
+    depths: [DepthStats; DEPTH_BUCKETS],
+}
+
+/// The rounds starting in a bucket of depth.
+#[derive(Debug, Clone, Copy, Default)]
+struct DepthStats {
+    /// The shoes that deal cards in the bucket.
+    shoes: u64,
+    rounds: u64,
+    /// The rounds where any offered bet has positive ex.
+    positive_rounds: u64,
+    /// The rounds where the policy bets.
+    bet_rounds: u64,
+    expected_profit: f64,
+}
+
+/// The current shoe.
+#[derive(Debug, Clone, Default)]
+struct ShoeRecord {
+    rounds: u32,
+    cards_dealt: u32, // Including the burned cards.
+    cards_before_cut: Option<u32>,
+    expected_profit: f64,
+    realized_profit: i64,
+}
+
+impl ThreadStats for ShoeStats {
+    fn merge(self, other: Self) -> Self {
+        let mut depths = self.depths;
+        for (x, y) in depths.iter_mut().zip(other.depths) {
+            x.shoes += y.shoes;
+            x.rounds += y.rounds;
+            x.positive_rounds += y.positive_rounds;
+            x.bet_rounds += y.bet_rounds;
+            x.expected_profit += y.expected_profit;
+        }
+        Self {
+            rounds: self.rounds + other.rounds,
+            min_rounds: match (self.min_rounds, other.min_rounds) {
+                (Some(x), Some(y)) => Some(x.min(y)),
+                (x, y) => x.or(y),
+            },
+            max_rounds: self.max_rounds.max(other.max_rounds),
+            cards_dealt: self.cards_dealt + other.cards_dealt,
+            cards_after_cut: self.cards_after_cut + other.cards_after_cut,
+            expected_profit: self.expected_profit + other.expected_profit,
+            realized_profit: self.realized_profit + other.realized_profit,
+            realized_profit_squared: self.realized_profit_squared + other.realized_profit_squared,
+            depths,
+        }
+    }
+}
+
+impl ShoeStats {
+    fn add_shoe(&mut self, shoe: &ShoeRecord, shoe_size: u32) {
+        self.rounds += shoe.rounds as u64;
+        self.min_rounds = Some(self.min_rounds.map_or(shoe.rounds, |x| x.min(shoe.rounds)));
+        self.max_rounds = self.max_rounds.max(shoe.rounds);
+        self.cards_dealt += shoe.cards_dealt as u64;
+        let cards_before_cut = shoe.cards_before_cut.unwrap_or(shoe.cards_dealt);
+        self.cards_after_cut += (shoe.cards_dealt - cards_before_cut) as u64;
+        self.expected_profit += shoe.expected_profit;
+        self.realized_profit += shoe.realized_profit;
+        self.realized_profit_squared += (shoe.realized_profit as f64).powi(2);
+        // The last card dealt is in the deepest bucket reached.
+        let deepest = get_bucket(shoe.cards_dealt.saturating_sub(1), shoe_size);
+        for depth in &mut self.depths[..=deepest] {
+            depth.shoes += 1;
+        }
+    }
+}
+
+fn get_bucket(cards_dealt: u32, shoe_size: u32) -> usize {
+    (cards_dealt as usize * DEPTH_BUCKETS / shoe_size as usize).min(DEPTH_BUCKETS - 1)
+}
+
+/// Bets by the simulator's gambler, and records the ex of its bets and of the
+/// rounds by depth.
+struct ShoeGambler<'a> {
+    gambler: SimulatorGambler<'a>,
+    rule: &'a Rule,
+    offered_bets: &'a [HandsBet],
+    counter: &'a ShoeCounter,
+    stats: &'a RefCell<ShoeStats>,
+    shoe: ShoeRecord,
+}
+
+impl<'a> GamblerProvider for ShoeGambler<'a> {
+    fn place_bet(&mut self, solution: &Solution) -> &HashMap<HandsBet, i64> {
+        let recording = self.counter.is_recording();
+        let bets = self.gambler.place_bet(solution);
+        if !recording {
+            return bets;
+        }
+
+        let expected_profit: f64 = bets
+            .iter()
+            .map(|(bet, money)| *money as f64 * solution.get_ex(*bet).unwrap_or(0.0))
+            .sum();
+        let positive = self
+            .offered_bets
+            .iter()
+            .any(|bet| solution.get_ex(*bet).is_some_and(|ex| ex > 0.0));
+        self.shoe.rounds += 1;
+        self.shoe.expected_profit += expected_profit;
+
+        let shoe_size = self.rule.number_of_decks * 52;
+        let mut stats = self.stats.borrow_mut();
+        let depth = &mut stats.depths[get_bucket(self.shoe.cards_dealt, shoe_size)];
+        depth.rounds += 1;
+        depth.positive_rounds += positive as u64;
+        depth.bet_rounds += !bets.is_empty() as u64;
+        depth.expected_profit += expected_profit;
+        bets
+    }
+    fn on_new_shoe(&mut self) {
+        if self.counter.is_recording() {
+            let shoe_size = self.rule.number_of_decks * 52;
+            self.stats.borrow_mut().add_shoe(&self.shoe, shoe_size);
+        }
+        self.counter.start_shoe();
+        self.shoe = Default::default();
+        self.gambler.on_new_shoe();
+    }
+    fn on_discard(&mut self, card: Card) {
+        // The face-up card, and as many cards as its value (10 for 0) are
+        // burned.
+        let burned = match card.to_bcr_value_index() {
+            0 => 10,
+            value => value as u32,
+        };
+        self.shoe.cards_dealt += 1 + burned;
+        self.gambler.on_discard(card);
+    }
+    fn on_round_start(&mut self) {
+        self.gambler.on_round_start();
+    }
+    fn on_round_end(&mut self, player: &Hand, banker: &Hand, round_result: &RoundResult) {
+        for hand in [player, banker] {
+            self.shoe.cards_dealt += 2 + hand.third.is_some() as u32;
+        }
+        self.shoe.realized_profit += round_result.total_profit;
+        self.gambler.on_round_end(player, banker, round_result);
+    }
+    fn on_cut_card_reached(&mut self, cards_before_cut: u32) {
+        // It's called for every card dealt after the cut card, too.
+        self.shoe.cards_before_cut.get_or_insert(cards_before_cut);
+        self.gambler.on_cut_card_reached(cards_before_cut);
+    }
+}
+
+fn print_report(rule: &Rule, shoes: u64, stats: &ShoeStats) {
+    let n = shoes.max(1) as f64;
+    let shoe_size = rule.number_of_decks * 52;
+    let cut_card = (shoe_size as f64 * rule.cut_card_proportion) as u32;
+    println!(
+        "{} shoes of {} decks, cut card at card {} ({:.1}%), {}discarding at start",
+        shoes,
+        rule.number_of_decks,
+        cut_card,
+        rule.cut_card_proportion * 100.0,
+        if rule.discard_at_start { "" } else { "not " }
+    );
+    println!(
+        "Rounds per shoe: {:.2} (min {}, max {})",
+        stats.rounds as f64 / n,
+        stats.min_rounds.unwrap_or(0),
+        stats.max_rounds
+    );
+    println!(
+        "Cards dealt per shoe: {:.2} ({:.1}% of the shoe), {:.2} of them after the cut card",
+        stats.cards_dealt as f64 / n,
+        stats.cards_dealt as f64 / n / shoe_size as f64 * 100.0,
+        stats.cards_after_cut as f64 / n
+    );
+    println!("Expected profit per shoe: {:.4}", stats.expected_profit / n);
+    let mean = stats.realized_profit as f64 / n;
+    let variance = (stats.realized_profit_squared / n - mean * mean).max(0.0);
+    println!(
+        "Realized profit per shoe: {:.4} (standard deviation {:.4})",
+        mean,
+        variance.sqrt()
+    );
+    println!();
+
+    // The depth curve, as CSV. The rates are in percent.
+    println!("depth,shoes_reaching,rounds_per_shoe,positive_ex_rounds,bet_rounds,expected_profit_per_shoe");
+    for (i, depth) in stats.depths.iter().enumerate() {
+        let rounds = depth.rounds.max(1) as f64;
+        println!(
+            "{}-{}%,{:.2},{:.4},{:.4},{:.4},{:.4}",
+            i * 100 / DEPTH_BUCKETS,
+            (i + 1) * 100 / DEPTH_BUCKETS,
+            depth.shoes as f64 / n * 100.0,
+            depth.rounds as f64 / n,
+            depth.positive_rounds as f64 / rounds * 100.0,
+            depth.bet_rounds as f64 / rounds * 100.0,
+            depth.expected_profit / n
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use rayon::prelude::*;
+use std::cell::Cell;
+use std::time::Instant;
+
+/// The stats that each thread of run_shoes() records on its own.
+pub(crate) trait ThreadStats: Send {
+    /// Adds up the stats of two threads.
+    fn merge(self, other: Self) -> Self;
+}
+
+/// Counts the shoes started by a game, so that only the given number of
+/// shoes are recorded.
+#[derive(Debug)]
+pub(crate) struct ShoeCounter {
+    started: Cell<u64>,
+    /// Stops recording after these many shoes.
+    shoes: u64,
+}
+
+impl ShoeCounter {
+    fn new(shoes: u64) -> Self {
+        Self {
+            started: Cell::new(0),
+            shoes,
+        }
+    }
+
+    /// Called by the gambler on every new shoe.
+    pub(crate) fn start_shoe(&self) {
+        self.started.set(self.started.get() + 1);
+    }
+
+    /// Whether the current shoe is one of the recorded ones. The rounds before
+    /// the first shoe starts aren't recorded, either.
+    pub(crate) fn is_recording(&self) -> bool {
+        let started = self.started.get();
+        started >= 1 && started <= self.shoes
+    }
+
+    /// Plays rounds until all the shoes are played.
+    pub(crate) fn play(&self, mut play_round: impl FnMut()) {
+        // The last round starts the shoe after the last one, and isn't recorded.
+        while self.started.get() <= self.shoes {
+            play_round();
+        }
+    }
+}
+
+/// Splits the shoes over all threads and merges the stats of the threads.
+/// `play` plays the shoes of the given counter on one thread.
+pub(crate) fn run_shoes<S: ThreadStats>(shoes: u64, play: impl Fn(&ShoeCounter) -> S + Sync) -> S {
+    let start = Instant::now();
+    let threads = rayon::current_num_threads() as u64;
+    let stats = (0..threads)
+        .into_par_iter()
+        .map(|i| {
+            play(&ShoeCounter::new(
+                shoes / threads + u64::from(i < shoes % threads),
+            ))
+        })
+        .reduce_with(S::merge)
+        .unwrap();
+    let seconds = start.elapsed().as_secs_f64();
+    eprintln!(
+        "{} shoes in {:.2}s ({:.1} shoes/second)",
+        shoes,
+        seconds,
+        shoes as f64 / seconds
+    );
+    stats
+}
@@ -1,27 +1,215 @@
-use baccarat::calculation::Solution;
+use crate::run_rounds;
+use baccarat::bet_sizing::{BetDistribution, StakeOptions};
+use baccarat::calculation::{incremental::IncrementalSolver, Counter};
+use baccarat::calculation::{
+    CachedSolver, ParallelSolver, Solution, SolutionCheminDeFer, Solver,
+    CHEMIN_DE_FER_BANKER_OPTIONS,
+};
 use baccarat::card::{Card, Shoe};
+use baccarat::counting::{CountingSystem, RunningCount};
 use baccarat::game::{
-    DealerProvider, GamblerProvider, Game, Hand, HandsBet, RoundResult, SolverProvider,
+    DealerProvider, DecisionProvider, GamblerProvider, Game, Hand, HandsBet, RoundResult,
+    SolverProvider,
 };
 use baccarat::Rule;
-use baccarat_drivers_lib::{ConfigBaccaratSimulator, MoneyStat};
+use baccarat_drivers_lib::{BetSizing, ConfigBaccaratSimulator, MoneyStat, SolverKind};
 use std::cell::RefCell;
 use std::collections::HashMap;
 
-pub fn start_simulation(rule: &Rule, config: &ConfigBaccaratSimulator) {
+/// The number of solutions the cached solver keeps.
+const CACHE_CAPACITY: usize = 1024;
+
+pub fn start_simulation(rule: &Rule, config: &ConfigBaccaratSimulator, rounds: Option<u64>) {
+    if config.counting.is_some() {
+        return simulate_with_solver(rule, config, NoSolver::new(rule), rounds);
+    }
+    match config.solver {
+        SolverKind::Simple => simulate_with_solver(rule, config, Solver::new(rule), rounds),
+        SolverKind::Parallel => {
+            simulate_with_solver(rule, config, ParallelSolver::new(rule), rounds)
+        }
+        SolverKind::Cached => {
+            let solver = CachedSolver::new(Solver::new(rule), CACHE_CAPACITY);
+            simulate_with_solver(rule, config, solver, rounds)
+        }
+        SolverKind::Incremental => {
+            let solver = IncrementalSolver::new(rule, &Counter::new(rule.number_of_decks));
+            simulate_with_solver(rule, config, solver, rounds)
+        }
+    }
+}
+
+/// Plays independent sessions of the given number of rounds, each starting
+/// with a new shoe and the configured bankroll, and prints the risk of ruin
+/// and the distributions of the bankroll and the drawdown.
+pub fn start_sessions(rule: &Rule, config: &ConfigBaccaratSimulator, sessions: u32, rounds: u64) {
+    let mut results = Vec::with_capacity(sessions as usize);
+    for _ in 0..sessions {
+        let result = if config.counting.is_some() {
+            play_session(rule, config, NoSolver::new(rule), rounds)
+        } else {
+            match config.solver {
+                SolverKind::Simple => play_session(rule, config, Solver::new(rule), rounds),
+                SolverKind::Parallel => {
+                    play_session(rule, config, ParallelSolver::new(rule), rounds)
+                }
+                SolverKind::Cached => {
+                    let solver = CachedSolver::new(Solver::new(rule), CACHE_CAPACITY);
+                    play_session(rule, config, solver, rounds)
+                }
+                SolverKind::Incremental => {
+                    let solver = IncrementalSolver::new(rule, &Counter::new(rule.number_of_decks));
+                    play_session(rule, config, solver, rounds)
+                }
+            }
+        };
+        results.push(result);
+    }
+    print_session_report(config.bankroll, rounds, &results);
+}
+
+fn simulate_with_solver<S: SolverProvider>(
+    rule: &Rule,
+    config: &ConfigBaccaratSimulator,
+    solver: S,
+    rounds: Option<u64>,
+) {
     let firsts = RefCell::new(None);
-    let dealer = SimulatorDealer::new(rule, &firsts);
-    let gambler = SimulatorGambler::new(rule, config, &firsts);
-    let solver = SimulatorSolver::new(rule);
-    let mut game = Game::new(rule, dealer, gambler, solver);
-    game.start_game_loop();
+    let money_stat = RefCell::new(MoneyStat::default());
+    let system = config.counting.as_ref().map(|c| c.get_system());
+    let dealer = SimulatorDealer::new(rule.number_of_decks, rule.cut_card_proportion, &firsts);
+    let gambler = SimulatorGambler::new(
+        rule,
+        config,
+        &firsts,
+        &money_stat,
+        GamblerMode::Log,
+        system.as_ref(),
+    );
+    let strategy = RefCell::new(None);
+    let solver = SimulatorSolver::new(solver, &strategy);
+    let decider = SimulatorDecider::new(&strategy);
+    let mut game = Game::with_decision_provider(rule, dealer, gambler, solver, decider);
+    run_rounds(rounds, || game.play_round());
+}
+
+/// The number of points on the bankroll curve of a session.
+const CURVE_POINTS: u64 = 10;
+
+#[derive(Debug, Clone)]
+struct SessionResult {
+    /// The bankroll after each CURVE_POINTS-th of the rounds.
+    curve: Vec<i64>,
+    max_drawdown: i64,
+    ruined: bool,
+}
+
+fn play_session<S: SolverProvider>(
+    rule: &Rule,
+    config: &ConfigBaccaratSimulator,
+    solver: S,
+    rounds: u64,
+) -> SessionResult {
+    let firsts = RefCell::new(None);
+    let money_stat = RefCell::new(MoneyStat::default());
+    let system = config.counting.as_ref().map(|c| c.get_system());
+    let dealer = SimulatorDealer::new(rule.number_of_decks, rule.cut_card_proportion, &firsts);
+    let gambler = SimulatorGambler::new(
+        rule,
+        config,
+        &firsts,
+        &money_stat,
+        GamblerMode::Session,
+        system.as_ref(),
+    );
+    let strategy = RefCell::new(None);
+    let solver = SimulatorSolver::new(solver, &strategy);
+    let decider = SimulatorDecider::new(&strategy);
+    let mut game = Game::with_decision_provider(rule, dealer, gambler, solver, decider);
+
+    let mut curve = Vec::with_capacity(CURVE_POINTS as usize);
+    let mut next_point = 1;
+    for round in 1..=rounds {
+        // A ruined gambler's bankroll doesn't change anymore.
+        if !money_stat.borrow().is_ruined() {
+            game.play_round();
+        }
+        if round * CURVE_POINTS >= next_point * rounds {
+            curve.push(config.bankroll + money_stat.borrow().cur_money());
+            next_point += 1;
+        }
+    }
+
+    let money_stat = money_stat.borrow();
+    SessionResult {
+        curve,
+        max_drawdown: money_stat.max_drawdown(),
+        ruined: money_stat.is_ruined(),
+    }
+}
+
+fn print_session_report(bankroll: i64, rounds: u64, results: &[SessionResult]) {
+    const PERCENTILES: [u32; 5] = [5, 25, 50, 75, 95];
+    let sessions = results.len();
+    let ruined = results.iter().filter(|r| r.ruined).count();
+    println!(
+        "{} sessions of {} rounds, starting with {}",
+        sessions, rounds, bankroll
+    );
+    println!(
+        "Risk of ruin: {:.2}% ({} sessions)",
+        ruined as f64 / sessions as f64 * 100.0,
+        ruined
+    );
+
+    let print_row = |name: &str, mut values: Vec<i64>| {
+        values.sort_unstable();
+        print!("{:>16}", name);
+        for q in PERCENTILES {
+            print!("{:>12}", get_percentile(&values, q));
+        }
+        println!();
+    };
+    print!("{:>16}", "Percentile");
+    for q in PERCENTILES {
+        print!("{:>12}", format!("{}%", q));
+    }
+    println!();
+    for i in 0..CURVE_POINTS as usize {
+        let round = (i as u64 + 1) * rounds / CURVE_POINTS;
+        let values = results.iter().filter_map(|r| r.curve.get(i).copied());
+        print_row(&format!("Round {}", round), values.collect());
+    }
+    print_row(
+        "Max drawdown",
+        results.iter().map(|r| r.max_drawdown).collect(),
+    );
+}
+
+/// The nearest-rank q-th percentile of the sorted values.
+fn get_percentile(sorted: &[i64], q: u32) -> i64 {
+    let rank = (sorted.len() * q as usize).div_ceil(100);
+    sorted.get(rank.max(1) - 1).copied().unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GamblerMode {
+    /// Prints the logs of the rounds with bets.
+    Log,
+    /// Doesn't print logs, and stops betting once the bankroll falls below the
+    /// ruin floor or can't afford the bets.
+    Session,
+    /// Doesn't print logs, and the bankroll is unlimited.
+    Quiet,
 }
 
 #[derive(Debug, Clone)]
-struct SimulatorGambler<'a> {
-    _rule: &'a Rule,
+pub(crate) struct SimulatorGambler<'a> {
+    rule: &'a Rule,
     config: &'a ConfigBaccaratSimulator,
     firsts: &'a RefCell<Option<Vec<Card>>>,
+    mode: GamblerMode,
+    count: Option<SimulatorCount<'a>>,
 
     bets: HashMap<HandsBet, i64>,
 
@@ -31,19 +219,28 @@ struct SimulatorGambler<'a> {
 
     // Records for log
     max_bets: ((HandsBet, f64), (HandsBet, f64)),
-    money_stat: MoneyStat,
+    money_stat: &'a RefCell<MoneyStat>,
 }
 
 impl<'a> SimulatorGambler<'a> {
-    fn new(
-        _rule: &'a Rule,
+    pub(crate) fn new(
+        rule: &'a Rule,
         config: &'a ConfigBaccaratSimulator,
         firsts: &'a RefCell<Option<Vec<Card>>>,
+        money_stat: &'a RefCell<MoneyStat>,
+        mode: GamblerMode,
+        counting_system: Option<&'a CountingSystem>,
     ) -> Self {
+        let count = counting_system.map(|system| {
+            let threshold = config.counting.as_ref().unwrap().threshold;
+            SimulatorCount::new(system, rule.number_of_decks, threshold)
+        });
         Self {
-            _rule,
+            rule,
             config,
             firsts,
+            mode,
+            count,
 
             bets: HashMap::new(),
 
@@ -55,7 +252,102 @@ impl<'a> SimulatorGambler<'a> {
                 (HandsBet::BankerWin, -f64::INFINITY),
                 (HandsBet::PlayerBonus, -f64::INFINITY),
             ),
-            money_stat: Default::default(),
+            money_stat,
+        }
+    }
+
+    fn get_bankroll(&self) -> i64 {
+        self.config.bankroll + self.money_stat.borrow().cur_money()
+    }
+
+    /// Bets the fractional Kelly stakes of the best main bet and side bet.
+    fn place_kelly_bets(&mut self, solution: &Solution) {
+        let bets: Vec<HandsBet> = [self.max_bets.0 .0, self.max_bets.1 .0]
+            .into_iter()
+            .filter(|bet| *bet != HandsBet::PlaceHolder)
+            .collect();
+        let bankroll = self.get_bankroll() as f64;
+        let stakes = BetDistribution::new(self.rule, solution, &bets)
+            .get_kelly_stakes(bankroll, self.config.kelly_fraction);
+        for (bet, stake) in bets.into_iter().zip(stakes) {
+            let stake = stake as i64;
+            if stake > 0 {
+                self.bets.insert(bet, stake);
+            }
+        }
+    }
+
+    /// Bets the optimal stakes of all the offered bets together.
+    fn place_optimal_bets(&mut self, solution: &Solution) {
+        let options = StakeOptions {
+            objective: self.config.objective,
+            bankroll: self.get_bankroll() as f64,
+            kelly_fraction: self.config.kelly_fraction,
+            min_stake: self.config.min_stake as f64,
+            max_stake: self.config.max_stake.map(|stake| stake as f64),
+        };
+        for stake in solution.get_optimal_bets(self.rule, &options) {
+            let money = stake.stake as i64;
+            if money > 0 && money >= self.config.min_stake {
+                self.bets.insert(stake.bet, money);
+            }
+        }
+    }
+
+    /// Chooses the bets according to the bet sizing, or the counting system
+    /// if there's one.
+    fn choose_bets(&mut self, solution: &Solution) {
+        if let Some(count) = &self.count {
+            if count.is_betting() {
+                let bet = count.running_count.get_system().bet.unwrap();
+                self.bets.insert(bet, self.main_bet_money);
+            }
+            return;
+        }
+
+        let max_bets = solution.get_best_main_side_bet(self.config.p_threshold);
+        self.max_bets = max_bets;
+
+        match self.config.bet_sizing {
+            BetSizing::Fixed => {}
+            BetSizing::Kelly => return self.place_kelly_bets(solution),
+            BetSizing::Optimal => return self.place_optimal_bets(solution),
+        }
+
+        // If side bet's ex < main bet's ex, we don't consider side bets.
+        if max_bets.1 .1 < max_bets.0 .1 {
+            if max_bets.0 .1 > 0.0 {
+                self.bets.insert(max_bets.0 .0, self.main_bet_money);
+            }
+        }
+        // If side bet's ex is greater, we may consider it.
+        else {
+            if 2.0 * max_bets.0 .1 + max_bets.1 .1 > 0.0 {
+                self.bets.insert(max_bets.0 .0, self.main_bet_money);
+                self.bets.insert(max_bets.1 .0, self.side_bet_money);
+            }
+        }
+    }
+
+    /// The same as choose_bets(), but the gambler is ruined rather than bets
+    /// more than the bankroll.
+    fn place_affordable_bets(&mut self, solution: &Solution) {
+        self.check_ruin();
+        if self.money_stat.borrow().is_ruined() {
+            return;
+        }
+        self.choose_bets(solution);
+        let stakes: i64 = self.bets.values().sum();
+        if stakes > self.get_bankroll() {
+            self.bets.clear();
+            self.money_stat.borrow_mut().ruin();
+        }
+    }
+
+    /// Ruins the gambler if the bankroll has fallen below the ruin floor.
+    fn check_ruin(&mut self) {
+        if self.get_bankroll() < self.config.get_ruin_floor() {
+            self.money_stat.borrow_mut().ruin();
         }
     }
 
@@ -66,14 +358,17 @@ impl<'a> SimulatorGambler<'a> {
             self.rounds % self.config.rounds_per_day
         );
 
-        println!(
-            "Max main bet: {:#?}({:.5}). Max side bet: {:#?}({:.5})",
-            self.max_bets.0 .0, self.max_bets.0 .1, self.max_bets.1 .0, self.max_bets.1 .1
-        );
+        match &self.count {
+            Some(count) => println!("True count: {:.3}", count.get_true_count()),
+            None => println!(
+                "Max main bet: {:?}({:.5}). Max side bet: {:?}({:.5})",
+                self.max_bets.0 .0, self.max_bets.0 .1, self.max_bets.1 .0, self.max_bets.1 .1
+            ),
+        }
 
         print!("Bet:");
         for (bet, money) in &self.bets {
-            print!(" ({:#?}, {})", bet, money);
+            print!(" ({:?}, {})", bet, money);
         }
         println!();
 
@@ -89,12 +384,13 @@ impl<'a> SimulatorGambler<'a> {
 
         println!("Final hands: {:#?} {:#?}", final_player, final_banker);
 
+        let money_stat = self.money_stat.borrow();
         println!(
             "Money: {}({}). Min: {}. Max: {}.",
-            self.money_stat.cur_money(),
+            money_stat.cur_money(),
             delta_money,
-            self.money_stat.min_money(),
-            self.money_stat.max_money()
+            money_stat.min_money(),
+            money_stat.max_money()
         );
 
         println!("----------------------------------");
@@ -103,15 +399,25 @@ impl<'a> SimulatorGambler<'a> {
 
 impl<'a> GamblerProvider for SimulatorGambler<'a> {
     fn on_new_shoe(&mut self) {
-        println!("NEW SHOE!!!!!!!");
-        println!("++++++++++++++++++++++++++++++++++");
+        if self.mode == GamblerMode::Log {
+            println!("NEW SHOE!!!!!!!");
+            println!("++++++++++++++++++++++++++++++++++");
+        }
         let mut firsts = self.firsts.borrow_mut();
         *firsts = None;
+        if let Some(count) = &mut self.count {
+            count.start_new_shoe(self.rule.number_of_decks);
+        }
     }
     fn on_cut_card_reached(&mut self, _cards_before_cut: u32) {}
     fn on_discard(&mut self, card: Card) {
-        println!("Discard: {:#?}", card);
-        println!("++++++++++++++++++++++++++++++++++");
+        if let Some(count) = &mut self.count {
+            count.add_dealt_card(card);
+        }
+        if self.mode == GamblerMode::Log {
+            println!("Discard: {:#?}", card);
+            println!("++++++++++++++++++++++++++++++++++");
+        }
     }
     fn on_round_start(&mut self) {
         let mut firsts = self.firsts.borrow_mut();
@@ -119,47 +425,84 @@ impl<'a> GamblerProvider for SimulatorGambler<'a> {
     }
     fn place_bet(&mut self, solution: &Solution) -> &HashMap<HandsBet, i64> {
         self.bets.clear();
-
-        let max_bets = solution.get_best_main_side_bet(self.config.p_threshold);
-        self.max_bets = max_bets;
-
-        // If side bet's ex < main bet's ex, we don't consider side bets.
-        if max_bets.1 .1 < max_bets.0 .1 {
-            if max_bets.0 .1 > 0.0 {
-                self.bets.insert(max_bets.0 .0, self.main_bet_money);
-            }
+        if self.mode == GamblerMode::Session {
+            self.place_affordable_bets(solution);
+        } else {
+            self.choose_bets(solution);
         }
-        // If side bet's ex is greater, we may consider it.
-        else {
-            if 2.0 * max_bets.0 .1 + max_bets.1 .1 > 0.0 {
-                self.bets.insert(max_bets.0 .0, self.main_bet_money);
-                self.bets.insert(max_bets.1 .0, self.side_bet_money);
-            }
-        }
-
         &self.bets
     }
     fn on_round_end(&mut self, player: &Hand, banker: &Hand, round_result: &RoundResult) {
-        self.money_stat.add(round_result.total_profit);
-        if self.bets.len() != 0 {
+        if let Some(count) = &mut self.count {
+            for hand in [player, banker] {
+                hand.initial
+                    .iter()
+                    .chain(&hand.third)
+                    .for_each(|card| count.add_dealt_card(*card));
+            }
+        }
+        self.money_stat.borrow_mut().add(round_result.total_profit);
+        if self.mode == GamblerMode::Session {
+            self.check_ruin();
+        }
+        if !self.bets.is_empty() && self.mode == GamblerMode::Log {
             self.print_log(round_result.total_profit, player, banker);
         }
         self.rounds += 1;
     }
 }
 
+/// The count of the cards seen in the current shoe.
+#[derive(Debug, Clone)]
+pub(crate) struct SimulatorCount<'a> {
+    running_count: RunningCount<'a>,
+    counter: Counter, // The cards not seen yet.
+    threshold: f64,
+}
+
+impl<'a> SimulatorCount<'a> {
+    pub(crate) fn new(system: &'a CountingSystem, number_of_decks: u32, threshold: f64) -> Self {
+        Self {
+            running_count: RunningCount::new(system),
+            counter: Counter::new(number_of_decks),
+            threshold,
+        }
+    }
+
+    pub(crate) fn start_new_shoe(&mut self, number_of_decks: u32) {
+        self.running_count.reset();
+        self.counter = Counter::new(number_of_decks);
+    }
+
+    pub(crate) fn add_dealt_card(&mut self, card: Card) {
+        self.running_count.add_dealt_card(card);
+        self.counter.remove_card(card);
+    }
+
+    pub(crate) fn get_true_count(&self) -> f64 {
+        self.running_count.get_true_count(&self.counter)
+    }
+
+    /// Whether the true count is high enough to bet.
+    pub(crate) fn is_betting(&self) -> bool {
+        self.get_true_count() >= self.threshold
+    }
+}
+
 #[derive(Debug, Clone)]
-struct SimulatorDealer<'a> {
-    _rule: &'a Rule,
+pub struct SimulatorDealer<'a> {
     shoe: Shoe,
     firsts: &'a RefCell<Option<Vec<Card>>>,
 }
 
 impl<'a> SimulatorDealer<'a> {
-    fn new(rule: &'a Rule, firsts: &'a RefCell<Option<Vec<Card>>>) -> Self {
+    pub fn new(
+        number_of_decks: u32,
+        cut_card_proportion: f64,
+        firsts: &'a RefCell<Option<Vec<Card>>>,
+    ) -> Self {
         Self {
-            _rule: rule,
-            shoe: Shoe::new(rule.number_of_decks, rule.cut_card_proportion),
+            shoe: Shoe::new(number_of_decks, cut_card_proportion),
             firsts,
         }
     }
@@ -193,20 +536,95 @@ impl<'a> DealerProvider for SimulatorDealer<'a> {
 }
 
 #[derive(Debug, Clone)]
-struct SimulatorSolver<'a> {
-    solver: baccarat::calculation::Solver<'a>,
+pub(crate) struct SimulatorSolver<'a, S: SolverProvider> {
+    solver: S,
+    strategy: &'a RefCell<Option<SolutionCheminDeFer>>,
 }
 
-impl<'a> SimulatorSolver<'a> {
-    fn new(rule: &'a Rule) -> Self {
-        Self {
-            solver: baccarat::calculation::Solver::new(rule),
+impl<'a, S: SolverProvider> SimulatorSolver<'a, S> {
+    pub(crate) fn new(solver: S, strategy: &'a RefCell<Option<SolutionCheminDeFer>>) -> Self {
+        Self { solver, strategy }
+    }
+}
+
+impl<'a, S: SolverProvider> SolverProvider for SimulatorSolver<'a, S> {
+    fn solve(&mut self, counter: &Counter) -> &Solution {
+        let solution = self.solver.solve(counter);
+        *self.strategy.borrow_mut() = solution.sol_chemin_de_fer.clone();
+        solution
+    }
+}
+
+/// Doesn't solve at all, for gamblers who bet by counting systems.
+#[derive(Debug, Clone, Default)]
+struct NoSolver {
+    solution: Solution,
+}
+
+impl NoSolver {
+    fn new(rule: &Rule) -> Self {
+        if rule.chemin_de_fer {
+            panic!("Counting systems don't support Chemin de fer");
         }
+        Default::default()
+    }
+}
+
+impl SolverProvider for NoSolver {
+    fn solve(&mut self, _counter: &Counter) -> &Solution {
+        &self.solution
+    }
+}
+
+/// Plays the options in Chemin de fer with the optimal strategy found by the
+/// solver for the current round.
+#[derive(Debug, Clone)]
+pub(crate) struct SimulatorDecider<'a> {
+    strategy: &'a RefCell<Option<SolutionCheminDeFer>>,
+}
+
+impl<'a> SimulatorDecider<'a> {
+    pub(crate) fn new(strategy: &'a RefCell<Option<SolutionCheminDeFer>>) -> Self {
+        Self { strategy }
     }
 }
 
-impl<'a> SolverProvider for SimulatorSolver<'a> {
-    fn solve(&mut self, counter: &baccarat::calculation::Counter) -> &Solution {
-        self.solver.solve(counter)
+impl<'a> DecisionProvider for SimulatorDecider<'a> {
+    fn should_player_draw(&mut self, _player: &Hand) -> bool {
+        let strategy = self.strategy.borrow();
+        let strategy = strategy.as_ref().unwrap();
+        rand::random::<f64>() < strategy.p_player_draw
+    }
+
+    fn should_banker_draw(&mut self, banker: &Hand, player_third: Card) -> bool {
+        let cell = (banker.get_sum(), player_third.to_bcr_value_index() as u8);
+        let i = CHEMIN_DE_FER_BANKER_OPTIONS
+            .iter()
+            .position(|option| *option == cell)
+            .unwrap();
+        let strategy = self.strategy.borrow();
+        let strategy = strategy.as_ref().unwrap();
+        rand::random::<f64>() < strategy.p_banker_draw[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles() {
+        let sorted: Vec<i64> = (1..=20).map(|x| x * 10).collect();
+        assert_eq!(get_percentile(&sorted, 0), 10);
+        assert_eq!(get_percentile(&sorted, 5), 10);
+        assert_eq!(get_percentile(&sorted, 25), 50);
+        assert_eq!(get_percentile(&sorted, 50), 100);
+        assert_eq!(get_percentile(&sorted, 51), 110);
+        assert_eq!(get_percentile(&sorted, 95), 190);
+        assert_eq!(get_percentile(&sorted, 100), 200);
+
+        assert_eq!(get_percentile(&[-7], 5), -7);
+        assert_eq!(get_percentile(&[-7], 95), -7);
+        assert_eq!(get_percentile(&[], 50), 0);
     }
 }
@@ -1,12 +1,19 @@
 use actix_web::{post, web, App, HttpResponse, HttpServer, Responder};
 use baccarat::{
-    calculation::{self, Counter, Solution},
-    Rule,
+    bet_sizing::StakeOptions,
+    calculation::{
+        self, conditional::KnownCards, incremental::IncrementalSolver, Counter,
+        OutcomeDistribution, Solution, SolutionCache,
+    },
+    card::Card,
+    dragon_tiger::{self, calculation::DragonTigerSolution, DragonTigerRule},
+    Error, Rule,
 };
-use baccarat_drivers_lib::parse_config_from_file;
+use baccarat_drivers_lib::{parse_config_from_file, GameKind};
 use clap::Parser;
 use rust_embed::RustEmbed;
-use std::{mem::MaybeUninit, sync::RwLock};
+use serde::Deserialize;
+use std::sync::{Mutex, RwLock};
 
 #[cfg(feature = "embed_website_assets")]
 use actix_web::get;
@@ -14,6 +21,7 @@ use actix_web::get;
 use mime_guess;
 
 const DEFAULT_CONFIG_PATH: &str = "~/.baccarat.yml";
+const SOLUTION_CACHE_CAPACITY: usize = 4096;
 
 #[derive(Debug, Parser)]
 #[command(author = "Jack Y. <seigino.mikata@outlook.com>")]
@@ -32,37 +40,176 @@ struct CommandLineArgs {
     port: Option<u16>,
 }
 
+/// The solver borrows the rule for as long as the service runs, so every rule
+/// the service is changed to is leaked. Rules are only changed by hand, and
+/// are small.
 #[derive(Debug)]
 struct ServiceState {
-    rule: RwLock<Rule>,
+    rule: RwLock<&'static Rule>,
+    cache: Mutex<SolutionCache>, // Solutions under the current rule.
+    // Tracks the counter of the last solve, which is usually only a few cards
+    // away from the next one during live play.
+    solver: Mutex<IncrementalSolver<'static>>,
+    dragon_tiger_rule: RwLock<DragonTigerRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OptimalBetsRequest {
+    card_count: Vec<u32>,
+    #[serde(default)]
+    options: StakeOptions,
+}
+
+/// The card count excludes the known cards.
+#[derive(Debug, Deserialize)]
+struct ConditionalSolveRequest {
+    card_count: Vec<u32>,
+    #[serde(default)]
+    player: KnownCardIndices,
+    #[serde(default)]
+    banker: KnownCardIndices,
+}
+
+/// The known cards of a hand, by card index.
+#[derive(Debug, Default, Deserialize)]
+struct KnownCardIndices {
+    #[serde(default)]
+    initial: [Option<usize>; 2],
+    #[serde(default)]
+    third: Option<usize>,
+}
+
+impl KnownCardIndices {
+    fn to_known_cards(&self) -> Result<KnownCards, Error> {
+        let to_card = |index: Option<usize>| index.map(Card::try_from_index).transpose();
+        Ok(KnownCards {
+            initial: [to_card(self.initial[0])?, to_card(self.initial[1])?],
+            third: to_card(self.third)?,
+        })
+    }
 }
 
 #[post("/api/solve")]
 async fn solve(card_count: web::Json<Vec<u32>>, state: web::Data<ServiceState>) -> impl Responder {
-    let rule = state.rule.read().unwrap();
-    let card_count = card_count.into_inner();
-    if card_count.len() != 52 {
-        return HttpResponse::BadRequest().body("Array length must be 52");
+    match get_solution(&state, &card_count) {
+        Ok(solution) => HttpResponse::Ok().json(solution),
+        Err(e) => bad_request(e),
     }
-    let card_count: [u32; 52] = unsafe {
-        let mut arr: [MaybeUninit<u32>; 52] = MaybeUninit::uninit().assume_init();
-        for (i, e) in card_count.iter().enumerate() {
-            arr[i] = MaybeUninit::new(*e);
-        }
-        std::mem::transmute_copy(&arr)
-    };
+}
+
+#[post("/api/optimal_bets")]
+async fn optimal_bets(
+    request: web::Json<OptimalBetsRequest>,
+    state: web::Data<ServiceState>,
+) -> impl Responder {
+    let rule = *state.rule.read().unwrap();
+    match get_solution(&state, &request.card_count) {
+        Ok(solution) => HttpResponse::Ok().json(solution.get_optimal_bets(rule, &request.options)),
+        Err(e) => bad_request(e),
+    }
+}
+
+/// Solves the card count, or gets the solution from the cache.
+fn get_solution(state: &ServiceState, card_count: &[u32]) -> Result<Solution, Error> {
+    let counter = Counter::try_with_card_count(card_count)?;
+    calculation::functional::check_counter(&counter)?;
+    if let Some(solution) = state.cache.lock().unwrap().get(&counter) {
+        return Ok(solution.clone());
+    }
+
+    // Holding the rule keeps it from changing in the middle of the solve.
+    let _rule = state.rule.read().unwrap();
+    let solution = state.solver.lock().unwrap().solve(&counter).clone();
+    state
+        .cache
+        .lock()
+        .unwrap()
+        .insert(counter, solution.clone());
+    Ok(solution)
+}
 
-    let mut counter = Counter::with_card_count(&card_count);
+#[post("/api/conditional_solve")]
+async fn conditional_solve(
+    request: web::Json<ConditionalSolveRequest>,
+    state: web::Data<ServiceState>,
+) -> impl Responder {
+    let rule = *state.rule.read().unwrap();
+    let result = Counter::try_with_card_count(&request.card_count).and_then(|counter| {
+        let player = request.player.to_known_cards()?;
+        let banker = request.banker.to_known_cards()?;
+        calculation::conditional::calculate(rule, &counter, &player, &banker)
+    });
+    match result {
+        Ok(solution) => HttpResponse::Ok().json(solution),
+        Err(e) => bad_request(e),
+    }
+}
+
+#[post("/api/solve_outcomes")]
+async fn solve_outcomes(
+    card_count: web::Json<Vec<u32>>,
+    state: web::Data<ServiceState>,
+) -> impl Responder {
+    let rule = *state.rule.read().unwrap();
+    let mut counter = match Counter::try_with_card_count(&card_count) {
+        Ok(counter) => counter,
+        Err(e) => return bad_request(e),
+    };
     let mut solution = Solution::default();
-    calculation::functional::calculate(&rule, &mut counter, &mut solution);
+    let mut outcomes = OutcomeDistribution::default();
+    if let Err(e) = calculation::functional::check_counter(&counter) {
+        return bad_request(e);
+    }
+    calculation::functional::par_calculate_with_outcomes(
+        rule,
+        &mut counter,
+        &mut solution,
+        &mut outcomes,
+    );
+    HttpResponse::Ok().json(outcomes)
+}
+
+#[post("/api/dragon_tiger/solve")]
+async fn dragon_tiger_solve(
+    card_count: web::Json<Vec<u32>>,
+    state: web::Data<ServiceState>,
+) -> impl Responder {
+    let rule = state.dragon_tiger_rule.read().unwrap();
+    let counter = match Counter::try_with_card_count(&card_count) {
+        Ok(counter) => counter,
+        Err(e) => return bad_request(e),
+    };
+    let mut solution = DragonTigerSolution::default();
+    if let Err(e) =
+        dragon_tiger::calculation::functional::try_calculate(&rule, &counter, &mut solution)
+    {
+        return bad_request(e);
+    }
     HttpResponse::Ok().json(solution)
 }
 
+fn bad_request(e: Error) -> HttpResponse {
+    HttpResponse::BadRequest().body(e.to_string())
+}
+
 #[post("/api/change_rule")]
 async fn change_rule(new_rule: web::Json<Rule>, state: web::Data<ServiceState>) -> impl Responder {
     let mut rule = state.rule.write().unwrap();
-    let new_rule: Rule = new_rule.into_inner().into();
+    let new_rule: &'static Rule = Box::leak(Box::new(new_rule.into_inner()));
     *rule = new_rule;
+    let mut solver = state.solver.lock().unwrap();
+    *solver = IncrementalSolver::new(new_rule, solver.get_counter());
+    state.cache.lock().unwrap().clear();
+    HttpResponse::Ok()
+}
+
+#[post("/api/dragon_tiger/change_rule")]
+async fn dragon_tiger_change_rule(
+    new_rule: web::Json<DragonTigerRule>,
+    state: web::Data<ServiceState>,
+) -> impl Responder {
+    let mut rule = state.dragon_tiger_rule.write().unwrap();
+    *rule = new_rule.into_inner();
     HttpResponse::Ok()
 }
 
@@ -135,24 +282,41 @@ async fn main() -> std::io::Result<()> {
 
     let c = &config.baccarat_solver_service;
     println!(
-        "Baccarat Solver Service running at {}:{}",
-        c.listening_ip, c.listening_port
+        "Baccarat Solver Service ({:?}) running at {}:{}",
+        config.game, c.listening_ip, c.listening_port
     );
 
-    let rule: Rule = config.rule;
+    let game = config.game;
+    let rule: &'static Rule = Box::leak(Box::new(config.rule));
     let state = web::Data::new(ServiceState {
-        rule: RwLock::new(rule.clone()),
+        rule: RwLock::new(rule),
+        cache: Mutex::new(SolutionCache::new(SOLUTION_CACHE_CAPACITY)),
+        solver: Mutex::new(IncrementalSolver::new(
+            rule,
+            &Counter::new(rule.number_of_decks),
+        )),
+        dragon_tiger_rule: RwLock::new(config.dragon_tiger_rule),
     });
     HttpServer::new(move || {
-        let app = App::new()
-            .app_data(state.clone())
-            .service(solve)
-            .service(change_rule);
+        let app = App::new().app_data(state.clone());
+        match game {
+            GameKind::Baccarat => {
+                let app = app
+                    .service(solve)
+                    .service(solve_outcomes)
+                    .service(conditional_solve)
+                    .service(optimal_bets)
+                    .service(change_rule);
 
-        #[cfg(feature = "embed_website_assets")]
-        let app = app.service(index).service(dist);
+                #[cfg(feature = "embed_website_assets")]
+                let app = app.service(index).service(dist);
 
-        app
+                app
+            }
+            GameKind::DragonTiger => app
+                .service(dragon_tiger_solve)
+                .service(dragon_tiger_change_rule),
+        }
     })
     .bind((&c.listening_ip as &str, c.listening_port))?
     .run()
@@ -1,17 +1,107 @@
-use baccarat::Rule;
+use baccarat::{
+    bet_sizing::Objective, counting::CountingSystem, dragon_tiger::DragonTigerRule, Rule,
+};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    #[serde(default)]
+    pub game: GameKind,
     pub rule: Rule,
+    #[serde(default)]
+    pub dragon_tiger_rule: DragonTigerRule,
     pub baccarat_simulator: ConfigBaccaratSimulator,
     pub baccarat_solver_service: ConfigBaccaratSolverService,
 }
 
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GameKind {
+    #[default]
+    Baccarat,
+    DragonTiger,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConfigBaccaratSimulator {
     pub rounds_per_day: u32,
     pub p_threshold: f64,
+
+    /// All solvers give the same solutions, but the parallel one makes use of
+    /// all CPU cores, the cached one reuses the solutions of the counters
+    /// solved recently, and the incremental one only updates the values of the
+    /// cards dealt since the last round.
+    #[serde(default)]
+    pub solver: SolverKind,
+
+    #[serde(default)]
+    pub bet_sizing: BetSizing,
+    /// The bankroll at the start. Fixed bet sizing doesn't care about it.
+    #[serde(default = "default_bankroll")]
+    pub bankroll: i64,
+    /// The proportion of the full Kelly stakes to bet, e.g. 0.5 for half Kelly.
+    #[serde(default = "default_kelly_fraction")]
+    pub kelly_fraction: f64,
+
+    /// What optimal bet sizing maximizes.
+    #[serde(default)]
+    pub objective: Objective,
+    /// The table minimum of each bet. Only optimal bet sizing cares about it.
+    #[serde(default)]
+    pub min_stake: i64,
+    /// The table maximum of each bet. Only optimal bet sizing cares about it.
+    #[serde(default)]
+    pub max_stake: Option<i64>,
+    /// A session is ruined once the bankroll falls below it. Defaults to
+    /// min_stake (at least 1), i.e. the gambler can't afford a single bet.
+    #[serde(default)]
+    pub ruin_floor: Option<i64>,
+
+    /// Bet by a card counting system instead of the solver, if it's given.
+    #[serde(default)]
+    pub counting: Option<ConfigCounting>,
+}
+
+/// Bets 200 on the bet of the counting system whenever its true count is at
+/// least the threshold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigCounting {
+    pub system: CountingSystemChoice,
+    pub threshold: f64,
+}
+
+/// Either the name of a preset or an EOR count (see CountingSystem::by_name()),
+/// or a custom counting system.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CountingSystemChoice {
+    Named(String),
+    Custom(CountingSystem),
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SolverKind {
+    #[default]
+    Simple,
+    Parallel,
+    Cached,
+    Incremental,
+}
+
+/// How much to bet on the best main bet and the best side bet.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BetSizing {
+    /// 200 on the main bet and 100 on the side bet.
+    #[default]
+    Fixed,
+    /// The fractional Kelly stakes of both bets together, which takes the
+    /// correlation between them into account.
+    Kelly,
+    /// The stakes of all the offered bets optimized together, subject to the
+    /// table limits.
+    Optimal,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +112,28 @@ pub struct ConfigBaccaratSolverService {
     pub listening_port: u16,
 }
 
+impl ConfigBaccaratSimulator {
+    pub fn get_ruin_floor(&self) -> i64 {
+        self.ruin_floor.unwrap_or(self.min_stake.max(1))
+    }
+}
+
+impl ConfigCounting {
+    /// Panics if the named count doesn't exist, or the system doesn't have a
+    /// bet.
+    pub fn get_system(&self) -> CountingSystem {
+        let system = match &self.system {
+            CountingSystemChoice::Named(name) => CountingSystem::by_name(name)
+                .unwrap_or_else(|| panic!("No counting system called {}", name)),
+            CountingSystemChoice::Custom(system) => system.clone(),
+        };
+        if system.bet.is_none() {
+            panic!("Counting system {} doesn't have a bet", system.name);
+        }
+        system
+    }
+}
+
 /// Reads the content of a given config file and parses it to a Config.
 ///
 /// Panics if any error occurs.
@@ -30,11 +142,15 @@ pub fn parse_config_from_file(filename: &str) -> Config {
     serde_yaml::from_str(&file_content).unwrap()
 }
 
+/// The money won (or lost, if negative) since the start.
 #[derive(Debug, Clone)]
 pub struct MoneyStat {
     cur: i64,
     min: i64,
     max: i64,
+    peak: i64, // Including the start.
+    max_drawdown: i64,
+    ruined: bool,
 }
 
 impl Default for MoneyStat {
@@ -43,6 +159,9 @@ impl Default for MoneyStat {
             cur: 0,
             min: i64::MAX,
             max: i64::MIN,
+            peak: 0,
+            max_drawdown: 0,
+            ruined: false,
         }
     }
 }
@@ -56,6 +175,13 @@ impl MoneyStat {
         if self.max < self.cur {
             self.max = self.cur;
         }
+        self.peak = self.peak.max(self.cur);
+        self.max_drawdown = self.max_drawdown.max(self.peak - self.cur);
+    }
+
+    /// Marks that the gambler is out of the game.
+    pub fn ruin(&mut self) {
+        self.ruined = true;
     }
 
     pub fn cur_money(&self) -> i64 {
@@ -69,6 +195,23 @@ impl MoneyStat {
     pub fn max_money(&self) -> i64 {
         self.max
     }
+
+    /// The largest fall from a previous peak (or the start).
+    pub fn max_drawdown(&self) -> i64 {
+        self.max_drawdown
+    }
+
+    pub fn is_ruined(&self) -> bool {
+        self.ruined
+    }
+}
+
+fn default_bankroll() -> i64 {
+    100000
+}
+
+fn default_kelly_fraction() -> f64 {
+    1.0
 }
 
 fn default_listening_ip() -> String {
@@ -78,3 +221,33 @@ fn default_listening_ip() -> String {
 fn default_listening_port() -> u16 {
     8080
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn money_stat_max_drawdown() {
+        let mut money_stat = MoneyStat::default();
+        assert_eq!(money_stat.max_drawdown(), 0);
+
+        // Falls from the start.
+        money_stat.add(-300);
+        assert_eq!(money_stat.max_drawdown(), 300);
+
+        // A new peak doesn't reset the max drawdown.
+        money_stat.add(1000);
+        assert_eq!(money_stat.max_drawdown(), 300);
+        assert_eq!(money_stat.cur_money(), 700);
+
+        // Falls from the new peak.
+        money_stat.add(-500);
+        money_stat.add(-200);
+        assert_eq!(money_stat.max_drawdown(), 700);
+        money_stat.add(600);
+        assert_eq!(money_stat.max_drawdown(), 700);
+
+        assert_eq!(money_stat.min_money(), -300);
+        assert_eq!(money_stat.max_money(), 700);
+    }
+}